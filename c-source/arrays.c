@@ -0,0 +1,70 @@
+#define ROWS 2
+enum { COLUMNS = 3 };
+
+// Array lengths are integer constant expressions
+struct grid {
+    int cells[ROWS * COLUMNS];
+};
+
+int first_column(int rows[][COLUMNS], int count) {
+    int total = 0;
+    for (int i = 0; i < count; i++) {
+        total += rows[i][0];
+    }
+    return total;
+}
+
+int sum(int values[], int length) {
+    int total = 0;
+    for (int i = 0; i < length; i++) {
+        total += values[i];
+    }
+    return total;
+}
+
+long trace(long matrix[3][3]) {
+    return matrix[0][0] + matrix[1][1] + matrix[2][2];
+}
+
+int main() {
+    int a[5] = {1, 2, 3};
+    a[4] = 10;
+    int total = sum(a, 5); // 16
+
+    int b[] = {4, 5, 6, 7};
+    int *p = b;
+    p++;
+    total += *p + p[2] + 2[b]; // 16 + 5 + 7 + 6 = 34
+
+    long m[3][3] = {{1, 2, 3}, 4, 5, 6, {7}};
+    total += trace(m) + m[2][1]; // 34 + 1 + 5 + 0 = 40
+
+    char c[2][3] = {1, 2, 3, 4, 5};
+    total += c[1][1] - c[1][2]; // 45
+
+    short s[4];
+    for (int i = 0; i < 4; i++) {
+        s[i] = i * 100;
+    }
+    total += (s[3] - s[1]) / 100; // 47
+
+    int *last = &a[4];
+    total += last - a; // 51
+
+    total += sizeof a + sizeof b[0] + sizeof m + sizeof m[0] + sizeof c; // 51 + 20 + 4 + 72 + 24 + 6 = 177
+
+    int g[ROWS + 1][COLUMNS] = {{1}, {2}, {3}};
+    int (*row)[COLUMNS] = g + 1;
+    total += first_column(g, ROWS + 1) + (*row)[0]; // 177 + 6 + 2 = 185
+    struct grid grid;
+    int d[2 * 3];
+    int e[sizeof(int)];
+    char h[COLUMNS > 2 ? 5 : 1];
+    total += sizeof grid + sizeof d + sizeof e + sizeof h; // 185 + 24 + 24 + 16 + 5 = 254
+    {
+        enum { COLUMNS = 7 };
+        char inner[(COLUMNS)];
+        total -= sizeof inner + sizeof(int[ROWS]); // 254 - 7 - 8 = 239
+    }
+    return total;
+}
@@ -0,0 +1,9 @@
+int main() {
+	int a = 1;
+	int b = 2;
+	int c = 3;
+	a = b = c + 4;
+	c = (a = a + 1) * 2;
+	b = b;
+	return a + b + c;
+}
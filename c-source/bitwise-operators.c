@@ -0,0 +1,5 @@
+int main() {
+	int a = 12;
+	int b = 10;
+	return a & b | 1 ^ 7 + ~a + 16;
+}
@@ -0,0 +1,35 @@
+int shadow(int a) {
+	{
+		int a = 10;
+	}
+	return a;
+}
+
+int main() {
+	int a = 1;
+	{
+		int a = 2;
+		{
+			int a = a + 3;
+		}
+	}
+	{
+		int b = 20;
+	}
+	{
+		int c = 30;
+		if (c == 30) {
+			int d = 40;
+		}
+	}
+	for (int i = 2; i; ) {
+		int j = i * 3;
+		break;
+	}
+	int i = 7;
+	if (a == 1) {
+		int a = 20;
+		return a + i + shadow(5);
+	}
+	return 0;
+}
@@ -0,0 +1,7 @@
+int main() {
+	int letters = 'z' - 'a';
+	int escapes = '\n' + '\t' + '\0' + '\\' + '\'' + '\"' + '\?' + '\a' + '\b' + '\f' + '\v' + '\r';
+	int numeric = '\x41' - '\101' + '\x7f' + '\7';
+	int negative = '\xff' + '\200';
+	return letters + escapes + numeric + negative;
+}
@@ -0,0 +1,13 @@
+// A program with comments in every position the lexer has to skip them
+int add(int a, /* the second operand */ int b) {
+	return a + b; // trailing comment
+}
+
+/*
+ * A block comment spanning
+ * multiple lines, containing // and /* and * characters
+ */
+int main() {
+	int a = 10 /* inline */ / 2; // division next to a comment
+	return add(a, 3) * 2 /**/;
+}
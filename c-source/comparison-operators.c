@@ -0,0 +1,11 @@
+int main() {
+	int a = 3;
+	int b = 5;
+	int lt = a < b;
+	int le = a <= 3;
+	int gt = b > a;
+	int ge = b >= 6;
+	int eq = a == 3;
+	int ne = a != a;
+	return lt + 2 * le + 4 * gt + 8 * ge + 16 * eq + 32 * ne;
+}
@@ -0,0 +1,22 @@
+int main() {
+	int x = 10;
+	int result = 0;
+	x += 5;
+	x -= 3;
+	x *= 4;
+	x /= 6;
+	x %= 5;
+	result += x;
+	x = 3;
+	x <<= 4;
+	x >>= 2;
+	result += x;
+	x = 12;
+	x &= 10;
+	x |= 1;
+	x ^= 3;
+	result += x;
+	int y = 2;
+	result += y *= 3;
+	return result;
+}
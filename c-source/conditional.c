@@ -0,0 +1,91 @@
+struct pair {
+    int first;
+    int second;
+};
+
+enum limits { SMALL = sizeof(int) == 4 ? 10 : 20, LARGE = SMALL > 5 ? SMALL * 10 : 0 };
+
+static int table[3] = {1 ? 7 : 8, 0 ? 7 : 8, SMALL < LARGE ? -1 : 1};
+
+int calls = 0;
+
+int count(int value) {
+    calls++;
+    return value;
+}
+
+int sign(long value) {
+    return value < 0 ? -1 : value > 0 ? 1 : 0;
+}
+
+int add(int a, int b) {
+    return a + b;
+}
+
+int classify(int value) {
+    switch (value) {
+    case SMALL > 0 ? 1 : 2:
+        return 100;
+    case LARGE:
+        return 200;
+    }
+    return 0;
+}
+
+int main() {
+    int total = 0;
+
+    // Only the selected value is evaluated
+    total += 1 ? count(5) : count(100); // 5
+    total += 0 ? count(100) : count(6); // 11
+    total += calls; // 13
+
+    // Nested conditionals associate to the right
+    total += sign(-5) + sign(0) + sign(123456789012); // 13
+    int a = 3;
+    total += a == 1 ? 100 : a == 2 ? 200 : a == 3 ? 3 : 400; // 16
+
+    // The values are converted to their common type, so -1 becomes unsigned
+    unsigned u = 1;
+    total += (a ? -1 : u) > 0; // 17
+    long big = 1 ? (char)-1 : 4000000000; // the char is converted to long
+    total += big == -1; // 18
+    total += sizeof(a ? (char)1 : (short)2) == 4; // 19
+
+    // A null pointer constant becomes a pointer of the other type
+    int *pointer = a ? &total : 0;
+    *pointer += 1; // 20
+    int *none = a ? 0 : &total;
+    total += none == 0; // 21
+
+    // Structs are selected as a whole
+    struct pair x = {1, 2};
+    struct pair y = {30, 40};
+    struct pair chosen = a > 2 ? y : x;
+    total += chosen.first + chosen.second; // 91
+    total += (a < 2 ? x : y).first; // 121
+
+    // The comma operator evaluates to its right operand
+    int b = (a = 4, a + 1);
+    total += a + b; // 130
+    total += add((a++, a), 2); // 137
+    for (int i = 0, j = 10; i < j; i++, j--) {
+        total += 1; // 142
+    }
+    int numbers[4] = {1, 2, 3, 4};
+    total += sizeof(0, numbers) == 8; // the array decays to a pointer, 143
+
+    total += SMALL + LARGE; // 253
+    total += table[0] + table[1] + table[2]; // 267
+    total += classify(1) + classify(100) + classify(7); // 567
+
+    // An assignment in either value, and a conditional in a condition
+    int c = 0;
+    a ? (c = 1) : (c = 2);
+    total += c; // 568
+    if (c ? 0 : 1) {
+        total += 1000;
+    }
+
+    return total % 256; // 568 % 256 = 56
+}
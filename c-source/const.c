@@ -0,0 +1,69 @@
+typedef const int constant;
+
+struct point {
+    const int x;
+    int y;
+};
+
+const int limit = 10;
+const char message[] = "const";
+constant primes[4] = {2, 3, 5, 7};
+static const struct point origin = {1, 2};
+
+// The characters are only read through the pointer to const
+int length(const char *s) {
+    int count = 0;
+    while (*s) {
+        s++;
+        count++;
+    }
+    return count;
+}
+
+int sum(const int *values, const int count) {
+    int total = 0;
+    for (int i = 0; i < count; i++) {
+        total += values[i];
+    }
+    return total;
+}
+
+int next(void) {
+    static const int step = 3;
+    static int current = 0;
+    current += step;
+    return current;
+}
+
+int main(void) {
+    int total = 0;
+
+    total += limit; // 10
+    total += length(message) + length("ab"); // 17
+    total += sum(primes, 4); // 34
+    total += origin.x + origin.y; // 37
+
+    // A const pointer may not be changed, but what it points to may
+    int value = 5;
+    int *const pointer = &value;
+    *pointer += 1;
+    total += value; // 43
+
+    // A pointer to const may point elsewhere
+    const int *reader = &primes[0];
+    reader++;
+    total += *reader; // 46
+
+    const struct point p = {4, 5};
+    struct point q = {6, 7};
+    q.y = p.x + p.y;
+    total += q.x + q.y; // 61
+
+    const int local = total * 2;
+    total += local / 61; // 63
+    total += sizeof(const int) + sizeof(constant) + sizeof(message); // 77
+    total += (const int)1; // 78
+    total += next() + next(); // 87
+
+    return total;
+}
@@ -0,0 +1,7 @@
+int main() {
+	int a = 100 / 7;
+	int b = 100 % 7;
+	int c = -7 / 2;
+	int d = -7 % 2;
+	return a * 10 + b + c + d;
+}
@@ -0,0 +1,97 @@
+// Divisions by a variable use the division instruction, by a constant a
+// multiplication, so both are compared over a range of dividends
+int check_int(int x, int d7, int dm3, int d8, int dm16, int d1000) {
+    int wrong = 0;
+
+    wrong += x / 7 != x / d7;
+    wrong += x % 7 != x % d7;
+    wrong += x / -7 != -(x / d7);
+    wrong += x % -7 != x % d7;
+    wrong += x / -3 != x / dm3;
+    wrong += x % -3 != x % dm3;
+    wrong += x / 8 != x / d8;
+    wrong += x % 8 != x % d8;
+    wrong += x / -16 != x / dm16;
+    wrong += x % -16 != x % dm16;
+    wrong += x / 1000 != x / d1000;
+    wrong += x % 1000 != x % d1000;
+
+    return wrong;
+}
+
+int check_unsigned(unsigned x, unsigned d7, unsigned d8, unsigned big) {
+    int wrong = 0;
+
+    wrong += x / 7 != x / d7;
+    wrong += x % 7 != x % d7;
+    wrong += x / -3000000000u != x / -big;
+    wrong += x / 8u != x / d8;
+    wrong += x % 8u != x % d8;
+    wrong += x / 3000000000u != x / big;
+    wrong += x % 3000000000u != x % big;
+
+    return wrong;
+}
+
+int check_long(long x, long d7, long dm10, long d3, unsigned long big) {
+    int wrong = 0;
+
+    wrong += x / 7 != x / d7;
+    wrong += x % 7 != x % d7;
+    wrong += x / -10 != x / dm10;
+    wrong += x % -10 != x % dm10;
+    wrong += x / 3 != x / d3;
+
+    unsigned long u = x;
+    wrong += u / 7 != u / d7;
+    wrong += u % 7 != u % d7;
+    wrong += u / 10000000000000000000ul != u / big;
+    wrong += u % 10000000000000000000ul != u % big;
+
+    return wrong;
+}
+
+int main() {
+    int total = 0;
+
+    // Division rounds towards zero, so the remainder takes the sign of the dividend
+    int a = -7;
+    int b = 2;
+    total += a / b == -3; // 1
+    total += a % b == -1; // 2
+    total += 7 / -2 == -3 && 7 % -2 == 1; // 3
+    total += -7 / 2 == -3 && -7 % 2 == -1; // 4
+    total += a / 2 == -3 && a % 2 == -1; // 5
+    total += a / -1 == 7 && a % 1 == 0; // 6
+
+    // Unsigned operands divide their values, not the signed bit patterns
+    unsigned u = -7;
+    total += u / 2 == 2147483644u; // 7
+    total += u % 10 == 9; // 8
+    unsigned long ul = -1;
+    total += ul / 3 == 6148914691236517205ul; // 9
+
+    int wrong = 0;
+    for (int x = -3000; x <= 3000; x += 7) {
+        wrong += check_int(x, 7, -3, 8, -16, 1000);
+        wrong += check_unsigned(x, 7, 8, 3000000000u);
+        wrong += check_long(x * 1000003l, 7, -10, 3, 10000000000000000000ul);
+    }
+    int extremes[4] = {2147483647, -2147483647 - 1, 2147483646, -2147483647};
+    for (int i = 0; i < 4; i++) {
+        wrong += check_int(extremes[i], 7, -3, 8, -16, 1000);
+        wrong += check_unsigned(extremes[i], 7, 8, 3000000000u);
+        wrong += check_long(extremes[i] * 4294967298l, 7, -10, 3, 10000000000000000000ul);
+    }
+    total += wrong == 0; // 10
+
+    // Compound assignments divide in the type of the operation too
+    long l = -100;
+    l /= 7;
+    total += l == -14; // 11
+    char c = -100;
+    c %= 7;
+    total += c == -2; // 12
+
+    return total * 10 + wrong; // 120
+}
@@ -0,0 +1,7 @@
+int main() {
+	do {
+		continue;
+		return 1;
+	} while (0);
+	return 5;
+}
@@ -0,0 +1,10 @@
+int main() {
+	int a = 10 - 2 - 3;
+	int b = 64 / 4 / 2;
+	int c = 1 < 2 < 3;
+	int d = 3 > 2 > 1;
+	int e = 5 == 5 == 1;
+	int f = 100 % 7 % 3;
+	int g = 256 >> 2 >> 3;
+	return a + b * 2 + c * 4 + d * 8 + e * 16 + f * 32 + g;
+}
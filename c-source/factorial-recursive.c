@@ -0,0 +1,10 @@
+int factorial(int n) {
+	if (n)
+		return n * factorial(n - 1);
+	else
+		return 1;
+}
+
+int main() {
+	return factorial(5);
+}
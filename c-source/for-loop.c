@@ -0,0 +1,9 @@
+int main() {
+	for (;;) {
+		break;
+	}
+	for (int i = 3; i; ) {
+		return i * 2;
+	}
+	return 0;
+}
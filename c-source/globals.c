@@ -0,0 +1,84 @@
+struct point {
+    int x;
+    int y;
+};
+
+int counter;
+int counter; // a tentative definition can be repeated
+long limit = 1000 * 1000 + 24;
+char small = 300; // wraps around to 44
+int table[5] = {1, 2, 3};
+struct point origin = {4, 5};
+struct point corners[2] = {{1, 2}, {3, 4}};
+int *third = &table[2];
+int *past = table + 5;
+int *y = &origin.y;
+static int hidden = 8;
+static struct point uninitialized;
+
+int next() {
+    static int calls;
+    static int step = 2;
+    calls += step;
+    return calls;
+}
+
+int other() {
+    static int calls = 100; // not the same variable as calls in next
+    return ++calls;
+}
+
+int read_later() {
+    extern int later;
+    return later;
+}
+
+int later = 7;
+
+static int helper(void);
+
+static int helper(void) {
+    return 3;
+}
+
+int helper(void); // keeps the internal linkage of the first declaration
+
+// The names of registers are ordinary identifiers in C
+int ch = 3;
+static char bl[2] = {4, 5};
+long *sp = &limit;
+
+int di(int si, int dx) {
+    static int cl = 2;
+    return si - dx + cl;
+}
+
+int main() {
+    int total = 0;
+
+    counter = 3;
+    total += counter + small; // 3 + 44 = 47
+    total += limit - 1000000; // 47 + 24 = 71
+
+    for (int i = 0; i < 5; i++) {
+        total += table[i]; // 71 + 6 = 77
+    }
+    total += origin.x + corners[1].y; // 77 + 4 + 4 = 85
+
+    *third = 10;
+    total += table[2] + *y + (past - table); // 85 + 10 + 5 + 5 = 105
+
+    next();
+    next();
+    total += next(); // 105 + 6 = 111
+    other();
+    total += other(); // 111 + 102 = 213
+
+    total += read_later() + hidden; // 213 + 7 + 8 = 228
+    uninitialized.y = 2;
+    total += uninitialized.x + uninitialized.y; // 228 + 2 = 230
+
+    total += ch + bl[1] + di(10, 4) + (*sp == limit); // 230 + 3 + 5 + 8 + 1 = 247
+    total += helper(); // 247 + 3 = 250
+    return total;
+}
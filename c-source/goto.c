@@ -0,0 +1,100 @@
+typedef int status;
+
+// The usual cleanup pattern, every failure jumps to the code that undoes the
+// steps taken so far
+int setup(int fail_at, int *undone) {
+    int step = 0;
+
+    step++;
+    if (fail_at == 1) {
+        goto fail_first;
+    }
+    step++;
+    if (fail_at == 2) {
+        goto fail_second;
+    }
+    step++;
+    if (fail_at == 3) {
+        goto fail_third;
+    }
+    return step;
+
+fail_third:
+    *undone += 100;
+fail_second:
+    *undone += 10;
+fail_first:
+    *undone += 1;
+    return -step;
+}
+
+// A backwards jump makes a loop
+int sum_to(int n) {
+    int total = 0;
+    int i = 1;
+
+again:
+    total += i;
+    i++;
+    if (i <= n) {
+        goto again;
+    }
+
+    return total;
+}
+
+// Jumps out of nested blocks and loops, and into a block
+int search(int target) {
+    int found = 0;
+
+    for (int i = 0; i < 10; i++) {
+        for (int j = 0; j < 10; j++) {
+            int product = i * j;
+            if (product == target) {
+                found = i * 10 + j;
+                goto done;
+            }
+        }
+    }
+    goto not_found;
+
+    {
+        int unused = 5;
+    not_found:
+        found = -1;
+    }
+
+done:
+    return found;
+}
+
+// Labels have a namespace of their own, so a typedef name can be a label
+int namespaces() {
+    int status = 0;
+    goto status;
+    status = 100;
+status:
+    status += 4;
+    {
+        goto end;
+    end:
+    }
+    return status;
+}
+
+int main() {
+    int undone = 0;
+    int total = 0;
+
+    total += setup(0, &undone); // 3
+    total += setup(1, &undone); // 3 - 1 = 2
+    total += setup(2, &undone); // 2 - 2 = 0
+    total += setup(3, &undone); // 0 - 3 = -3
+    total += undone; // -3 + 1 + 11 + 111 = 120
+    total += sum_to(10); // 175
+    total += search(42); // 175 + 67 = 242
+    total += search(97) + 1; // 242
+    total += namespaces(); // 246
+
+    return total;
+}
@@ -0,0 +1,10 @@
+int main() {
+	int a = 0;
+	if (a)
+		return 1;
+	else if (a + 1) {
+		int b = 7;
+		return b;
+	} else
+		return 3;
+}
@@ -0,0 +1,7 @@
+int add3(int a, int b, int c) {
+    return a + b + c;
+}
+
+int header_line() {
+    return __LINE__;
+}
@@ -0,0 +1,10 @@
+#ifndef PREPROCESSOR_H
+#define PREPROCESSOR_H
+
+// Looked for next to this header first
+#include "preprocessor-helpers.h"
+
+#define HEADER_VALUE 100
+#define SQUARE(x) ((x) * (x))
+
+#endif
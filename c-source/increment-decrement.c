@@ -0,0 +1,16 @@
+int main() {
+	int i = 5;
+	int a = i++;
+	int b = ++i;
+	int c = i--;
+	int d = --i;
+	int sum = 0;
+	for (int j = 0; j < 4; j++) {
+		sum += j;
+	}
+	int k = 3;
+	while (k--) {
+		sum++;
+	}
+	return a + b * 2 + c * 3 + d * 4 + sum * 10 + -k;
+}
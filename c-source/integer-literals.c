@@ -0,0 +1,8 @@
+int main() {
+	int hex = 0x1F + 0XaB;
+	int octal = 017 + 0;
+	int binary = 0b101 + 0B11;
+	int suffixes = 10u + 3L + 2ll + 4ULL + 5lu;
+	int large = 0xFFFFFFFFFF >> 36;
+	return hex + octal + binary + suffixes + large;
+}
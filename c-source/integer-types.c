@@ -0,0 +1,54 @@
+char add_chars(char a, char b) {
+	return a + b;
+}
+
+unsigned short halve(unsigned short x) {
+	return x / 2;
+}
+
+long long widen(int x) {
+	return x;
+}
+
+int main() {
+	int result = 0;
+
+	char c = 127;
+	c++;
+	if (c == -128)
+		result += 1;
+
+	unsigned char uc = 255;
+	uc += 2;
+	if (uc == 1)
+		result += 2;
+
+	short s = 32767;
+	s = s + 1;
+	if (s == -32768)
+		result += 4;
+
+	unsigned int u = 0;
+	u--;
+	if (u == 4294967295u && u > 0)
+		result += 8;
+
+	int big = 2147483647;
+	unsigned int wrapped = big + 1u;
+	if (wrapped == 2147483648u)
+		result += 16;
+
+	long l = 2147483647;
+	l = l + 1;
+	if (l == 2147483648)
+		result += 32;
+
+	_Bool b = 42;
+	if (b == 1)
+		result += 64;
+
+	if (add_chars(100, 100) == -56 && halve(65535) == 32767 && widen(-1) == -1)
+		result += 128;
+
+	return result;
+}
@@ -0,0 +1,3 @@
+int main() {
+	return 100 - 20 - 30 - 1 + 2 * 3 - 24 / 4 / 2;
+}
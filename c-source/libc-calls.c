@@ -0,0 +1,43 @@
+int printf(char *format, ...);
+int sprintf(char *buffer, char *format, ...);
+unsigned long strlen(char *s);
+int strcmp(char *a, char *b);
+int puts(char *s);
+void *malloc(unsigned long size);
+void free(void *pointer);
+
+struct pair {
+    long first;
+    long second;
+};
+
+long sum(struct pair p, int a, int b, int c, int d, int e, int f, int g) {
+    return p.first + p.second + a + b + c + d + e + f + g;
+}
+
+int main() {
+    char buffer[32];
+    int total = 0;
+
+    total += printf("%d %s %c %ld\n", 42, "text", 'x', 1234567890123); // 24
+    total += sprintf(buffer, "%d-%d", 12, 345); // 24 + 6 = 30
+    total += strlen(buffer) + (strcmp(buffer, "12-345") == 0); // 30 + 6 + 1 = 37
+    puts(buffer);
+
+    // The calls are made with values pushed on the stack, and with arguments
+    // on the stack
+    total += 1 + printf("%s\n", "nested"); // 37 + 1 + 7 = 45
+    struct pair p = {1, 2};
+    total += sum(p, 1, 2, 3, 4, printf("a\n"), 6, printf("bc\n")); // 45 + 3 + 10 + 2 + 6 + 3 = 69
+    total += printf("%d %d %d %d %d %d %d %d\n", 1, 2, 3, 4, 5, 6, 7, 8); // 69 + 16 = 85
+
+    // The allocated memory is returned as a pointer to void
+    int *numbers = malloc(4 * sizeof(int));
+    for (int i = 0; i < 4; i++) {
+        numbers[i] = i;
+    }
+    total += numbers[3]; // 88
+    free(numbers);
+
+    return total;
+}
@@ -0,0 +1,12 @@
+int fail() {
+	return 1 / 0;
+}
+
+int main() {
+	int a = 0 && fail();
+	int b = 1 || fail();
+	int c = !a + !!7;
+	int d = 2 && 3;
+	int e = 0 || 0;
+	return a + 2 * b + 4 * c + 16 * d + 32 * e;
+}
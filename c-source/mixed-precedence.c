@@ -0,0 +1,7 @@
+int main() {
+	int a = 1 + 2 * 3 - 4 / 2 % 3;
+	int b = -2 * -3 + !0 * 4 - ~1;
+	int c = 1 | 2 ^ 3 & 4 << 1 == 8 || 0 && 1;
+	int d = (1 | 2) ^ (3 & 4) << (1 == 1);
+	return a * 16 + b + c + d;
+}
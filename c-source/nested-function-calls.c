@@ -0,0 +1,15 @@
+int add(int a, int b) {
+	return a + b;
+}
+
+int negate(int a) {
+	return -a;
+}
+
+int zero() {
+	return 0;
+}
+
+int main() {
+	return add((1 + 2), add(3, negate(-4)) * (zero() + 2)) - -negate(zero() - 1);
+}
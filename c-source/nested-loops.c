@@ -0,0 +1,16 @@
+int main() {
+	int result = 9;
+	while (1) {
+		for (;;) {
+			do {
+				break;
+			} while (1);
+			break;
+		}
+		if (0) {
+			continue;
+		}
+		break;
+	}
+	return result;
+}
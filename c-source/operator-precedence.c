@@ -0,0 +1,3 @@
+int main() {
+	return 10 - 2 - 3 + 1 == 6 && 8 / 2 / 2 == 2 | 1 << 2 ^ 3 & 5 * 2 % 3;
+}
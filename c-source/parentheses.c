@@ -0,0 +1,5 @@
+int main() {
+	int a = (2 + 3) * (4 - 1);
+	int b = ((((1 + 2)) * 3) - (4 - (2 - 1)));
+	return a * (b + (1));
+}
@@ -0,0 +1,46 @@
+int swap(int *a, int *b) {
+	int tmp = *a;
+	*a = *b;
+	*b = tmp;
+	return 0;
+}
+
+int set_through(int **pp, int *target) {
+	*pp = target;
+	**pp += 5;
+	return **pp;
+}
+
+int main() {
+	int x = 3;
+	int y = 10;
+	swap(&x, &y);
+
+	int z = 1;
+	int *p = &x;
+	int result = set_through(&p, &z);
+
+	char c = 'a';
+	char *pc = &c;
+	(*pc)++;
+	*pc += 1;
+
+	int *q = p + 4;
+	long l = 0;
+	long *r = &l;
+	long *s = r + 3;
+	long *null = 0;
+
+	if (q - p == 4 && p < q && q > p && p != q && !(p == q))
+		result += 10;
+	if (s - r == 3)
+		result += 20;
+	if (null == 0 && !null && s && r != null)
+		result += 40;
+
+	int *first = &x;
+	int *second = &y;
+	*first = *second * 2 + *&x;
+
+	return result + x + y + (c - 'a') + *p;
+}
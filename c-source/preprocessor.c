@@ -0,0 +1,56 @@
+#include <preprocessor.h>
+#include "include/preprocessor.h" // the include guard skips it the second time
+
+#define STR(x) #x
+#define XSTR(x) STR(x)
+#define CAT(a, b) a ## b
+#define EMPTY
+#define COUNT 3
+#define TWICE(f, x) f(f(x))
+#define INC(x) ((x) + 1)
+#define CALL(f, ...) f(__VA_ARGS__)
+#define LINEAR(a, b) \
+    ((a) * 10 + (b))
+
+int main() {
+    int total = 0;
+
+    total += SQUARE(1 + 2); // 9
+    total += sizeof STR(a "b\n" 'c') + sizeof XSTR(COUNT); // 9 + 12 + 2 = 23
+    int CAT(var, 1) = 5;
+    total += var1 + CAT(1, 0) EMPTY; // 23 + 5 + 10 = 38
+    int twice = 2;
+#define twice (twice * 2) // not expanded again in its own expansion
+    total += twice; // 38 + 4 = 42
+    total += TWICE(INC, 1) + CALL(add3, 1, 2, 3); // 42 + 3 + 6 = 51
+    total += LINEAR(1, 2) + SQUARE(
+        2
+    ); // 51 + 12 + 4 = 67
+    total += __LINE__ - 29 + header_line(); // 67 + 6 = 73
+
+#if defined(COUNT) && COUNT * 2 == 6 && !defined UNDEFINED
+    total += 10; // 83
+#elif 1
+    total += 1000;
+#else
+    total += 2000;
+#endif
+
+#ifdef UNDEFINED
+    total += 1000;
+#elif (COUNT << 2) > 10 && (-1 < 0u) == 0 && 7 / 2 == 3 && (0 && 1 / 0) == 0
+    total += 20; // 103
+#endif
+
+#if 0
+#error not reached
+    'unterminated
+#endif
+
+#undef COUNT
+#ifndef COUNT
+    total += HEADER_VALUE; // 203
+#endif
+
+    return total;
+}
@@ -0,0 +1,6 @@
+int main() {
+	int a = 1 << 4 + 1;
+	int b = -64 >> 2;
+	int c = a >> 1 << 2;
+	return a + b + c;
+}
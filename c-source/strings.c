@@ -0,0 +1,59 @@
+struct person {
+    char name[8];
+    int age;
+};
+
+char *greeting = "Hello";
+char buffer[] = "data";
+char *tail = "abc" + 1;
+char *words[] = {"one", "two", "three"};
+
+long length(char *s) {
+    long n = 0;
+    while (s[n]) {
+        n++;
+    }
+    return n;
+}
+
+int print(char *s) {
+    return write(1, s, length(s));
+}
+
+int main() {
+    int total = 0;
+
+    print(greeting);
+    print(", " "world" "!\n");
+    print("tab\tquote\" backslash\\ \x41\102C\n");
+
+    char s[] = "hi";
+    char t[6] = "abc";
+    char u[3] = "xyz"; // no room for the terminating null character
+    total += sizeof s + sizeof t + sizeof u + sizeof "a\0b"; // 3 + 6 + 3 + 4 = 16
+    total += t[3] + t[5] + u[2] - 'z'; // 16
+
+    char *p = "pointer";
+    total += p[1] - 'o' + length(p); // 16 + 7 = 23
+    total += length(tail) + length(words[2]); // 23 + 2 + 5 = 30
+
+    buffer[0] = 'D';
+    print(buffer);
+    print("\n");
+    total += buffer[0] - 'D' + sizeof buffer; // 30 + 5 = 35
+
+    struct person bob = {"bob", 42};
+    total += bob.age + length(bob.name) + sizeof bob.name; // 35 + 42 + 3 + 8 = 88
+
+    char names[2][4] = {"ab", {"cd"}};
+    print(names[1]);
+    print("\n");
+    total += names[1][1] - 'c' + length(names[0]); // 88 + 1 + 2 = 91
+
+    char escapes[] = "\a\b\f\n\r\t\v\e\?\'\0";
+    for (int i = 0; i < sizeof escapes; i++) {
+        total += escapes[i]; // 91 + 7 + 8 + 12 + 10 + 13 + 9 + 11 + 27 + 63 + 39 = 290
+    }
+
+    return total - 100; // 190
+}
@@ -0,0 +1,135 @@
+struct point {
+    int x;
+    int y;
+};
+
+struct padded {
+    char c;
+    long l;
+    short s;
+};
+
+struct triple {
+    char a;
+    short b;
+    char c;
+};
+
+struct big {
+    long values[3];
+    int tag;
+};
+
+union number {
+    char bytes[8];
+    int i;
+    long l;
+};
+
+struct node {
+    int value;
+    struct node *next;
+};
+
+struct point add(struct point a, struct point b) {
+    struct point sum = {a.x + b.x, a.y + b.y};
+    return sum;
+}
+
+struct padded make_padded(char c, long l, short s) {
+    struct padded result;
+    result.c = c;
+    result.l = l;
+    result.s = s;
+    return result;
+}
+
+struct big scale(struct big b, int factor) {
+    for (int i = 0; i < 3; i++) {
+        b.values[i] *= factor;
+    }
+    b.tag++;
+    return b;
+}
+
+int triple_sum(struct triple t) {
+    return t.a + t.b + t.c;
+}
+
+// The seventh and eighth arguments and the struct that no longer fits in the
+// remaining registers are passed on the stack
+long many(int a, int b, int c, int d, int e, struct point p, int f, struct big g) {
+    return a + b + c + d + e + p.x + p.y + f + g.values[2] + g.tag;
+}
+
+// The struct on the stack comes first, and the arguments after it use all of the
+// registers, including rcx
+int stack_first(struct big g, int a, int b, int c, int d, struct triple t) {
+    return g.tag + d + t.c;
+}
+
+int struct_in_rcx(struct big g, int a, int b, int c, struct triple t) {
+    return g.tag + t.a + t.b + t.c;
+}
+
+int length(struct node *list) {
+    int count = 0;
+    while (list) {
+        count++;
+        list = list->next;
+    }
+    return count;
+}
+
+int main() {
+    struct point p = {1, 2};
+    struct padded pad;
+    struct triple t = {1, 2, 3};
+    union number n;
+    struct big b = {{1, 2, 3}, 4};
+    int total = sizeof p + sizeof pad + sizeof t + sizeof n + sizeof b; // 8 + 24 + 6 + 8 + 32 = 78
+
+    struct point q;
+    q = p;
+    q.y = 10;
+    struct point r = add(p, q);
+    total += r.x + r.y; // 78 + 2 + 12 = 92
+
+    pad = make_padded(3, 100, 7);
+    total += pad.c + pad.l + pad.s; // 92 + 110 = 202
+
+    total += triple_sum(t); // 208
+
+    struct big scaled = scale(b, 2);
+    total += scaled.values[0] + scaled.values[2] + scaled.tag + b.values[2]; // 208 + 2 + 6 + 5 + 3 = 224
+
+    total += many(1, 2, 3, 4, 5, p, 6, b); // 224 + 15 + 3 + 6 + 3 + 4 = 255
+
+    n.l = 0;
+    n.i = 258;
+    total -= n.bytes[0] + n.bytes[1]; // 255 - 3 = 252
+
+    struct node third = {3, 0};
+    struct node second = {2, &third};
+    struct node first = {1, &second};
+    struct node *head = &first;
+    total -= length(head) + head->next->next->value; // 252 - 6 = 246
+
+    struct point points[3] = {{1, 2}, {3, 4}};
+    struct point *pp = points;
+    pp++;
+    pp->x += 10;
+    total -= points[1].x + points[2].y; // 246 - 13 = 233
+
+    struct {
+        struct point corner;
+        int size;
+    } square = {{5, 6}, 7};
+    total -= square.corner.y + square.size; // 233 - 13 = 220
+
+    total -= add(p, p).y; // 220 - 4 = 216
+
+    total -= stack_first(b, 10, 20, 30, 40, t); // 216 - 47 = 169
+    total -= struct_in_rcx(b, 10, 20, 30, t); // 169 - 10 = 159
+    return total;
+}
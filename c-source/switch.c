@@ -0,0 +1,136 @@
+enum day { MONDAY, TUESDAY, WEDNESDAY, THURSDAY, FRIDAY, SATURDAY, SUNDAY };
+
+// Dense cases, which jump through a table
+int weekday_hours(enum day day) {
+    switch (day) {
+    case MONDAY:
+    case TUESDAY:
+        return 8;
+    case WEDNESDAY:
+        return 6;
+    case THURSDAY:
+    case FRIDAY:
+        return 7;
+    default:
+        return 0;
+    }
+}
+
+// Sparse cases, which are compared in turn
+int sparse(long value) {
+    int result = 0;
+
+    switch (value) {
+    case -1000:
+        result = 1;
+        break;
+    case 7:
+        result = 2;
+        break;
+    case 5000000000:
+        result = 3;
+        break;
+    case 123456:
+        result = 4;
+    }
+
+    return result;
+}
+
+// Fallthrough from one case into the next, and a default in the middle
+int fallthrough(int value) {
+    int result = 0;
+
+    switch (value) {
+    case 1:
+        result += 1;
+    case 2:
+        result += 2;
+    default:
+        result += 4;
+    case 3:
+        result += 8;
+        break;
+    case 4:
+        result += 16;
+    }
+
+    return result;
+}
+
+// Break leaves the switch, continue the loop around it
+int loop(int count) {
+    int total = 0;
+
+    for (int i = 0; i < count; i++) {
+        switch (i % 4) {
+        case 0:
+            continue;
+        case 1:
+            total += 1;
+            break;
+        case 2: {
+            int doubled = i * 2;
+            total += doubled;
+            break;
+        }
+        default:
+            switch (i) {
+            case 3:
+                total += 100;
+                break;
+            case 7:
+                total += 200;
+            }
+            total += 1000;
+        }
+    }
+
+    return total;
+}
+
+// Duff's device, the cases jump into the body of the loop
+int duff(int count) {
+    int copied = 0;
+    int rounds = (count + 3) / 4;
+
+    switch (count % 4) {
+    case 0:
+        do {
+            copied++;
+        case 3:
+            copied++;
+        case 2:
+            copied++;
+        case 1:
+            copied++;
+        } while (--rounds > 0);
+    }
+
+    return copied;
+}
+
+int main() {
+    int total = 0;
+
+    total += weekday_hours(MONDAY) + weekday_hours(WEDNESDAY) + weekday_hours(FRIDAY)
+        + weekday_hours(SUNDAY); // 8 + 6 + 7 + 0 = 21
+    total += sparse(-1000) + sparse(7) + sparse(5000000000) + sparse(123456) + sparse(8); // 21 + 10 = 31
+    total += fallthrough(1) + fallthrough(3) + fallthrough(4) + fallthrough(9); // 31 + 15 + 8 + 16 + 12 = 82
+    total += loop(8); // 82 + 1 + 4 + 1100 + 1 + 12 + 1200 = 2400
+    total += duff(11); // 2411
+
+    unsigned char small = 200;
+    switch (small) {
+    case 200:
+        total += 1; // 2412
+        break;
+    case -56:
+        total += 1000;
+    }
+
+    switch (total) {
+    }
+
+    return total % 256; // 2412 % 256 = 108
+}
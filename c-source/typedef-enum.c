@@ -0,0 +1,82 @@
+typedef int integer;
+typedef unsigned long size;
+typedef struct point {
+    integer x;
+    integer y;
+} Point, *PointPointer;
+typedef int Row[3];
+
+enum color { RED, GREEN = 5, BLUE, ALPHA = BLUE * 2 };
+enum { FIRST = -1, SECOND, THIRD };
+
+typedef struct node Node;
+struct node {
+    int value;
+    Node *next;
+};
+
+static const integer step = 1;
+long volatile unsigned static counter;
+
+integer sum_points(Point a, PointPointer b) {
+    return a.x + a.y + b->x + b->y;
+}
+
+int count(int, Node *);
+
+int count(int total, Node *node) {
+    if (!node) {
+        return total;
+    }
+    return count(total + node->value, node->next);
+}
+
+int main() {
+    Point p = {1, 2};
+    PointPointer pp = &p;
+    integer total = sum_points(p, pp); // 6
+
+    // A typedef name in an inner scope is hidden by a variable of the same name
+    {
+        int integer = 10;
+        total += integer; // 16
+    }
+    integer again = 1;
+    total += again; // 17
+
+    // A typedef in a block only applies within it
+    {
+        typedef char integer;
+        integer c = 300;
+        total += c == 44; // 18
+    }
+
+    total += RED + GREEN + BLUE + ALPHA; // 18 + 0 + 5 + 6 + 12 = 41
+    total += FIRST + SECOND + THIRD; // 41 - 1 + 0 + 1 = 41
+    enum color color = BLUE;
+    total += color; // 47
+
+    Row rows[2] = {{1, 2, 3}, {4, 5, 6}};
+    int (*row)[3] = rows;
+    total += row[1][2] + sizeof(Row) + sizeof(rows) / sizeof(*rows); // 47 + 6 + 12 + 2 = 67
+
+    total += sizeof(Point) + sizeof(size) + sizeof(int *[4]) + sizeof(int (*)[4]); // 67 + 8 + 8 + 32 + 8 = 123
+    total += (char)300 + (unsigned char)-1 - 255; // 123 + 44 + 0 = 167
+    total += (int)(long)&p == (int)(long)pp; // 168
+    total += (_Bool)256 + (short)65537; // 168 + 1 + 1 = 170
+
+    Node third = {3, 0};
+    Node second = {2, &third};
+    Node first = {1, &second};
+    total += count(0, &first); // 176
+
+    counter += step;
+    const int *pointer = &total;
+    total += *pointer + counter - 176; // 177
+
+    for (integer i = 0; i < 3; i++) {
+        total += i; // 180
+    }
+
+    return total;
+}
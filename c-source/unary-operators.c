@@ -0,0 +1,8 @@
+int main() {
+	int a = - -5;
+	int b = -(-3);
+	int c = !-0 + !!-4;
+	int d = ~-1 + -~0;
+	int e = +-+2;
+	return a * 10 + b + c + d - e;
+}
@@ -0,0 +1,17 @@
+int main() {
+	int a;
+	int b, c;
+	a = 4;
+	b = a * 2;
+	c = b + a;
+	int d = 1, e, f = 3;
+	e = d + f;
+	for (int i = 0, j = 10; i < j; i++) {
+		j--;
+		d += i;
+	}
+	int x, y = 2, z;
+	z = y;
+	x = z + 1;
+	return a + b + c + d + e + x;
+}
@@ -0,0 +1,41 @@
+int main() {
+	int result = 0;
+
+	unsigned int u = 10;
+	int i = -1;
+	if (i > u)
+		result += 1;
+
+	long l = -1;
+	if (l < u)
+		result += 2;
+
+	unsigned int big = 4000000000u;
+	if (big / 3 == 1333333333u && big % 7 == 3)
+		result += 4;
+
+	unsigned int shifted = big >> 4;
+	if (shifted == 250000000u)
+		result += 8;
+
+	int negative = -64;
+	if ((negative >> 2) == -16)
+		result += 16;
+
+	unsigned long ul = 18446744073709551615ul;
+	if (ul / 2 == 9223372036854775807 && ul + 1 == 0)
+		result += 32;
+
+	unsigned short us = 65535;
+	int promoted = us + 1;
+	if (promoted == 65536)
+		result += 64;
+
+	signed char sc = -1;
+	unsigned char uc = sc;
+	long unsigned int x = uc;
+	if (x == 255 && -sc == 1)
+		result += 128;
+
+	return result;
+}
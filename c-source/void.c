@@ -0,0 +1,71 @@
+typedef void nothing;
+
+int counter = 0;
+
+void increment(int *value) {
+    *value += 1;
+}
+
+// Returns from the middle of the loop, without a value
+void count_to(int limit) {
+    while (1) {
+        if (counter >= limit) {
+            return;
+        }
+        counter++;
+    }
+}
+
+nothing reset(void) {
+    counter = 0;
+}
+
+// A function returning void may return a void expression
+void forward(int limit) {
+    return count_to(limit);
+}
+
+int get_ten(void) {
+    return 10;
+}
+
+// Copies the bytes through pointers to void
+void copy(void *target, void *source, int size) {
+    char *to = target;
+    char *from = source;
+    for (int i = 0; i < size; i++) {
+        to[i] = from[i];
+    }
+}
+
+int main(void) {
+    int total = 0;
+
+    increment(&total); // 1
+    count_to(5);
+    total += counter; // 6
+    forward(9);
+    total += counter; // 15
+    reset();
+    total += counter + get_ten(); // 25
+
+    // Pointers to void convert to and from the other pointers
+    long values[2] = {100, 0};
+    void *pointer = values;
+    long *longs = pointer;
+    total += longs[0]; // 125
+    copy(&values[1], &values[0], sizeof(long));
+    total += values[1] == 100; // 126
+    total += pointer == (void *)longs; // 127
+    void *none = 0;
+    total += none == 0; // 128
+
+    // A value cast to void is evaluated and discarded
+    (void)total;
+    (void)increment(&total); // 129
+    (void)(total += 10); // 139
+    total > 0 ? increment(&total) : reset(); // 140
+    total ? (void)0 : increment(&total);
+
+    return total;
+}
@@ -0,0 +1,10 @@
+int main() {
+	int a = 4;
+	while (a) {
+		int b = a * 3;
+		if (b)
+			break;
+		return 1;
+	}
+	return a;
+}
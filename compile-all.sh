@@ -3,4 +3,4 @@ if [ $# -ne 1 ]; then
 	exit -1
 fi
 
-ls c-source/ | cut -f1 -d'.' | parallel --jobs 1 make $1-{}
+ls c-source/ | grep '\.c$' | cut -f1 -d'.' | parallel --jobs 1 make $1-{}
@@ -1,8 +1,13 @@
 use std::fs;
+use std::process::ExitCode;
+use std::rc::Rc;
 
+use rust_c_compiler::error::CompileError;
 use rust_c_compiler::generator::{generate_assembly, generate_operations, print_operations};
 use rust_c_compiler::lexer::{print_tokens, tokenize, Token};
 use rust_c_compiler::parser::{parse_tokens, ParseNode, print_ast};
+use rust_c_compiler::preprocessor::preprocess;
+use rust_c_compiler::source::SourceFile;
 
 use clap::Parser;
 use rust_c_compiler::symbolpass::symbolpass;
@@ -12,51 +17,59 @@ use rust_c_compiler::symbolpass::symbolpass;
 struct Args {
     source_file: String,
     output_file: String,
+
+    /// Warn about reads of local variables that are never assigned a value
+    #[arg(long)]
+    warn_uninitialized: bool,
+
+    /// Emit main for the C library to call, instead of a _start entry point, so
+    /// that the binary can be linked with the C library
+    #[arg(long)]
+    libc: bool,
+
+    /// Add the directory to the directories searched for included files
+    #[arg(short = 'I')]
+    include_paths: Vec<String>,
 }
 
-fn read_file_contents(file_path: String) -> String {
-    let result = fs::read_to_string(file_path);
-    match result {
-        Ok(content) => content,
-        Err(error) => panic!("Problem opening the file: {:?}", error),
-    }
+fn read_file_contents(file_path: &str) -> Result<String, CompileError> {
+    fs::read_to_string(file_path).map_err(|error| CompileError::Io(file_path.to_string(), error))
 }
 
-fn write_to_file(file_path: String, content: String) {
-    fs::write(file_path, content).unwrap();
+fn write_to_file(file_path: &str, content: String) -> Result<(), CompileError> {
+    fs::write(file_path, content).map_err(|error| CompileError::Io(file_path.to_string(), error))
 }
 
-fn main() {
-    let args = Args::parse();
-    let content = read_file_contents(args.source_file);
+fn compile(args: Args) -> Result<(), CompileError> {
+    let content = read_file_contents(&args.source_file)?;
 
     println!("\nProgram:\n");
     println!("{}", content);
 
-    let tokens: Vec<Token> = match tokenize(content) {
-        Ok(lexed_tokens) => lexed_tokens,
-        Err(reason) => {
-            println!("Encountered error during the tokenizing step: {}", reason);
-            return;
-        }
-    };
+    let source = Rc::new(SourceFile::new(args.source_file, content));
+    let source = preprocess(&source, &args.include_paths)?;
+
+    println!("\nPreprocessed:\n");
+    println!("{}", source.content);
+
+    let tokens: Vec<Token> = tokenize(&source)?;
 
     println!("\nTokens:");
     print_tokens(&tokens);
 
-    let mut ast: ParseNode = match parse_tokens(tokens) {
-        Ok(parsed_ast) => parsed_ast,
-        Err(reason) => {
-            println!("Encountered error during the parser step: {}", reason);
-            return;
+    let mut ast: ParseNode = parse_tokens(tokens)?;
+    let warnings = symbolpass(&mut ast)?;
+
+    if args.warn_uninitialized {
+        for warning in warnings {
+            eprintln!("{}", warning);
         }
-    };
-    symbolpass(&mut ast);
+    }
 
     println!("\nAST Tree:");
     print_ast(&ast);
 
-    let operations = generate_operations(&ast);
+    let operations = generate_operations(&ast, args.libc)?;
     print_operations(&operations);
 
     let assembly = generate_assembly(&operations);
@@ -64,5 +77,17 @@ fn main() {
     println!("\nGenerated Assembly:");
     println!("{}", assembly);
 
-    write_to_file(args.output_file, assembly);
+    write_to_file(&args.output_file, assembly)
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    match compile(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::FAILURE
+        }
+    }
 }
@@ -0,0 +1,55 @@
+use std::fmt;
+use std::io;
+
+use crate::source::Span;
+
+#[derive(Debug)]
+pub enum CompileError {
+    Preprocess(Span, String), // span, message
+    Lex(Span, String),        // span, message
+    Parse(Span, String),      // span, message
+    Semantic(Span, String),   // span, message
+    Codegen(Span, String),    // span, message
+    Io(String, io::Error),    // path, error
+}
+
+impl CompileError {
+    pub fn get_span(&self) -> Option<&Span> {
+        match self {
+            CompileError::Preprocess(span, _)
+            | CompileError::Lex(span, _)
+            | CompileError::Parse(span, _)
+            | CompileError::Semantic(span, _)
+            | CompileError::Codegen(span, _) => Some(span),
+            CompileError::Io(_, _) => None,
+        }
+    }
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CompileError::Preprocess(span, message)
+            | CompileError::Lex(span, message)
+            | CompileError::Parse(span, message)
+            | CompileError::Semantic(span, message)
+            | CompileError::Codegen(span, message) => write!(f, "{}", span.render_error(message)),
+            CompileError::Io(path, error) => write!(f, "{}: error: {}", path, error),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CompileWarning {
+    Uninitialized(Span, String), // span, message
+}
+
+impl fmt::Display for CompileWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CompileWarning::Uninitialized(span, message) => {
+                write!(f, "{}", span.render_warning(message))
+            }
+        }
+    }
+}
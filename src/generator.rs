@@ -1,58 +1,310 @@
 use crate::constants::QUADWORD_LENGTH;
+use crate::error::CompileError;
 use crate::parser::{BinaryOp, NodeType, ParseNode, UnaryOp};
+use crate::types::CType;
+
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum Size {
+    Byte,
+    Word,
+    Dword,
+    Qword,
+}
+
+impl Size {
+    fn of(ctype: &CType) -> Size {
+        match ctype.size() {
+            1 => Size::Byte,
+            2 => Size::Word,
+            4 => Size::Dword,
+            _ => Size::Qword,
+        }
+    }
+
+    fn bytes(&self) -> usize {
+        match self {
+            Size::Byte => 1,
+            Size::Word => 2,
+            Size::Dword => 4,
+            Size::Qword => 8,
+        }
+    }
+
+    // The directive that defines data of this size
+    fn as_define(&self) -> String {
+        match self {
+            Size::Byte => "db".to_string(),
+            Size::Word => "dw".to_string(),
+            Size::Dword => "dd".to_string(),
+            Size::Qword => "dq".to_string(),
+        }
+    }
+
+    fn as_string(&self) -> String {
+        match self {
+            Size::Byte => "byte".to_string(),
+            Size::Word => "word".to_string(),
+            Size::Dword => "dword".to_string(),
+            Size::Qword => "qword".to_string(),
+        }
+    }
+}
 
 #[derive(Eq, PartialEq, Clone, Debug)]
 pub enum Param {
     Rax,
-    Rbx,
+    Eax,
+    Ax,
     Rcx,
+    Ecx,
+    Cx,
     Rdx,
+    Edx,
     Rsp,
     Rbp,
-    Constant(i32),
-    BpMinus(usize),
+    Al,
+    Cl,
+    Rdi,
+    Rsi,
+    R8,
+    R9,
+    R10,
+    R11,
+    Constant(i64),
+    RipRelative(String), // a label, addressed relative to the instruction pointer
+    Memory(Box<Memory>),
 }
 
 impl Param {
+    // The memory operand [base + displacement]
+    fn memory(size: Size, base: Param, displacement: i64) -> Param {
+        Param::Memory(Box::new(Memory {
+            size,
+            base,
+            index: None,
+            displacement,
+        }))
+    }
+
+    // Local variables live below the saved rbp, at their offset
+    fn local(offset: usize, ctype: &CType) -> Param {
+        Param::memory(Size::of(ctype), Param::Rbp, -(offset as i64))
+    }
+
+    // Static variables live at their label
+    fn global(label: &str, ctype: &CType) -> Param {
+        Param::memory(Size::of(ctype), Param::RipRelative(label.to_string()), 0)
+    }
+
+    // The part of a memory operand at the displacement from its address, accessing
+    // size bytes
+    fn offset(&self, displacement: i64, size: Size) -> Param {
+        let Param::Memory(memory) = self else {
+            panic!("Only memory operands have an offset, found {:?}", self);
+        };
+
+        Param::Memory(Box::new(Memory {
+            size,
+            displacement: memory.displacement + displacement,
+            ..(**memory).clone()
+        }))
+    }
+
     fn as_string(&self) -> String {
         match self {
             Param::Rax => "rax".to_string(),
-            Param::Rbx => "rbx".to_string(),
+            Param::Eax => "eax".to_string(),
+            Param::Ax => "ax".to_string(),
             Param::Rcx => "rcx".to_string(),
+            Param::Ecx => "ecx".to_string(),
+            Param::Cx => "cx".to_string(),
             Param::Rdx => "rdx".to_string(),
+            Param::Edx => "edx".to_string(),
             Param::Rsp => "rsp".to_string(),
             Param::Rbp => "rbp".to_string(),
+            Param::Al => "al".to_string(),
+            Param::Cl => "cl".to_string(),
+            Param::Rdi => "rdi".to_string(),
+            Param::Rsi => "rsi".to_string(),
+            Param::R8 => "r8".to_string(),
+            Param::R9 => "r9".to_string(),
+            Param::R10 => "r10".to_string(),
+            Param::R11 => "r11".to_string(),
             Param::Constant(x) => x.to_string(),
-            Param::BpMinus(x) => format!("[rbp - {}]", x),
+            Param::RipRelative(label) => format!("rel {}", escape_symbol(label)),
+            Param::Memory(memory) => {
+                format!("{} {}", memory.size.as_string(), memory.address_as_string())
+            }
+        }
+    }
+}
+
+// The memory operand [base + index * scale + displacement], accessing size bytes
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct Memory {
+    pub size: Size,
+    pub base: Param,
+    pub index: Option<(Param, usize)>, // register, scale
+    pub displacement: i64,
+}
+
+impl Memory {
+    fn address_as_string(&self) -> String {
+        let mut address = self.base.as_string();
+
+        if let Some((index, scale)) = &self.index {
+            address.push_str(&format!(" + {}*{}", index.as_string(), scale));
+        }
+
+        match self.displacement {
+            0 => {}
+            x if x < 0 => address.push_str(&format!(" - {}", -x)),
+            x => address.push_str(&format!(" + {}", x)),
+        }
+
+        format!("[{}]", address)
+    }
+}
+
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum Condition {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Below, // unsigned variants of the comparisons
+    BelowOrEqual,
+    Above,
+    AboveOrEqual,
+}
+
+impl Condition {
+    fn as_string(&self) -> String {
+        match self {
+            Condition::Equal => "e".to_string(),
+            Condition::NotEqual => "ne".to_string(),
+            Condition::Less => "l".to_string(),
+            Condition::LessOrEqual => "le".to_string(),
+            Condition::Greater => "g".to_string(),
+            Condition::GreaterOrEqual => "ge".to_string(),
+            Condition::Below => "b".to_string(),
+            Condition::BelowOrEqual => "be".to_string(),
+            Condition::Above => "a".to_string(),
+            Condition::AboveOrEqual => "ae".to_string(),
+        }
+    }
+}
+
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum Section {
+    Text,
+    Data,   // initialized static variables
+    Bss,    // static variables that start out as zero
+    Rodata, // string literals and jump tables
+}
+
+impl Section {
+    fn as_string(&self) -> String {
+        match self {
+            Section::Text => ".text".to_string(),
+            Section::Data => ".data".to_string(),
+            Section::Bss => ".bss".to_string(),
+            Section::Rodata => ".rodata".to_string(),
         }
     }
 }
 
 #[derive(Eq, PartialEq, Clone, Debug)]
 pub enum Asm {
+    Section(Section),
+    Global(String),
+    Extern(String),
+    Align(usize),
+    Alignb(usize), // aligns uninitialized data
+    Define(Size, String),
+    Resb(usize),
+    Times(usize, Box<Asm>),
     FunctionRef(String),
-    OSInterrupt, // Linux: always 0x80
+    Label(String),
+    Syscall,
     Mov(Param, Param),
+    Movzx(Param, Param),
+    Movsx(Param, Param),
+    Movsxd(Param, Param),
+    Lea(Param, Param),
     Neg(Param),
+    Not(Param),
     Add(Param, Param),
     Sub(Param, Param),
     Mul(Param, Param),
+    MulWide(Param),  // rdx:rax = rax * operand, unsigned
+    ImulWide(Param), // rdx:rax = rax * operand, signed
+    Cqo,
+    Cdq,
+    Idiv(Param),
+    Div(Param),
+    And(Param, Param),
+    Or(Param, Param),
+    Xor(Param, Param),
+    Shl(Param, Param),
+    Sar(Param, Param),
+    Shr(Param, Param),
+    Set(Condition, Param),
     Push(Param),
     Pop(Param),
+    Cmp(Param, Param),
+    Jmp(String),
+    JmpIndirect(Param), // jumps to the address in the operand
+    Jcc(Condition, String),
+    Call(String),
+    Leave,
+    Ret,
+}
+
+// NASM reads a symbol prefixed with $ as a symbol even if it is the name of a
+// register or instruction, as the identifiers `ch` or `sp` of a program would be.
+// The local labels start with a dot and are not identifiers of the program.
+fn escape_symbol(label: &str) -> String {
+    if label.starts_with('.') {
+        label.to_string()
+    } else {
+        format!("${}", label)
+    }
 }
 
 impl Asm {
     fn as_string(&self) -> String {
         match self {
-            Asm::FunctionRef(name) => {
-                let mut line = name.clone();
-                line.push(':');
-                line.to_owned()
-            }
-            Asm::OSInterrupt => "int 0x80".to_string(),
+            Asm::Section(section) => format!("section {}", section.as_string()),
+            Asm::Global(label) => format!("global {}", escape_symbol(label)),
+            Asm::Extern(label) => format!("extern {}", escape_symbol(label)),
+            Asm::Align(alignment) => format!("align {}", alignment),
+            Asm::Alignb(alignment) => format!("alignb {}", alignment),
+            Asm::Define(size, value) => format!("{} {}", size.as_define(), value),
+            Asm::Resb(size) => format!("resb {}", size),
+            Asm::Times(count, asm) => format!("times {} {}", count, asm.as_string()),
+            Asm::FunctionRef(name) => format!("{}:", escape_symbol(name)),
+            Asm::Label(label) => format!("{}:", escape_symbol(label)),
+            Asm::Syscall => "syscall".to_string(),
             Asm::Mov(first, second) => {
                 format!("mov {}, {}", first.as_string(), second.as_string())
             }
+            Asm::Movzx(first, second) => {
+                format!("movzx {}, {}", first.as_string(), second.as_string())
+            }
+            Asm::Movsx(first, second) => {
+                format!("movsx {}, {}", first.as_string(), second.as_string())
+            }
+            Asm::Movsxd(first, second) => {
+                format!("movsxd {}, {}", first.as_string(), second.as_string())
+            }
+            // The address of a memory operand is computed without accessing it, so it has no size
+            Asm::Lea(first, Param::Memory(memory)) => {
+                format!("lea {}, {}", first.as_string(), memory.address_as_string())
+            }
+            Asm::Lea(_, second) => panic!("lea needs a memory operand, found {:?}", second),
             Asm::Push(param) => {
                 format!("push {}", param.as_string())
             }
@@ -62,6 +314,9 @@ impl Asm {
             Asm::Neg(param) => {
                 format!("neg {}", param.as_string())
             }
+            Asm::Not(param) => {
+                format!("not {}", param.as_string())
+            }
             Asm::Add(first, second) => {
                 format!("add {}, {}", first.as_string(), second.as_string())
             }
@@ -71,6 +326,58 @@ impl Asm {
             Asm::Mul(first, second) => {
                 format!("imul {}, {}", first.as_string(), second.as_string())
             }
+            Asm::MulWide(param) => {
+                format!("mul {}", param.as_string())
+            }
+            Asm::ImulWide(param) => {
+                format!("imul {}", param.as_string())
+            }
+            Asm::Cqo => "cqo".to_string(),
+            Asm::Cdq => "cdq".to_string(),
+            Asm::Idiv(param) => {
+                format!("idiv {}", param.as_string())
+            }
+            Asm::Div(param) => {
+                format!("div {}", param.as_string())
+            }
+            Asm::And(first, second) => {
+                format!("and {}, {}", first.as_string(), second.as_string())
+            }
+            Asm::Or(first, second) => {
+                format!("or {}, {}", first.as_string(), second.as_string())
+            }
+            Asm::Xor(first, second) => {
+                format!("xor {}, {}", first.as_string(), second.as_string())
+            }
+            Asm::Shl(first, second) => {
+                format!("shl {}, {}", first.as_string(), second.as_string())
+            }
+            Asm::Sar(first, second) => {
+                format!("sar {}, {}", first.as_string(), second.as_string())
+            }
+            Asm::Shr(first, second) => {
+                format!("shr {}, {}", first.as_string(), second.as_string())
+            }
+            Asm::Set(condition, param) => {
+                format!("set{} {}", condition.as_string(), param.as_string())
+            }
+            Asm::Cmp(first, second) => {
+                format!("cmp {}, {}", first.as_string(), second.as_string())
+            }
+            Asm::Jmp(label) => {
+                format!("jmp {}", label)
+            }
+            Asm::JmpIndirect(param) => {
+                format!("jmp {}", param.as_string())
+            }
+            Asm::Jcc(condition, label) => {
+                format!("j{} {}", condition.as_string(), label)
+            }
+            Asm::Call(name) => {
+                format!("call {}", escape_symbol(name))
+            }
+            Asm::Leave => "leave".to_string(),
+            Asm::Ret => "ret".to_string(),
         }
     }
 }
@@ -101,7 +408,7 @@ impl AddSelfToAsmVec for &[Asm] {
     }
 }
 
-pub fn print_operations(operations: &Vec<Asm>) {
+pub fn print_operations(operations: &[Asm]) {
     println!("\nGenerated Operations:");
     for operation in operations {
         println!("{:?}", operation)
@@ -120,121 +427,1626 @@ macro_rules! join_asm  {
     };
 }
 
-pub fn generate_operations(node: &ParseNode) -> Vec<Asm> {
-    match &node.node_type {
-        NodeType::Fn(name, n_variables) => {
-            let function_name = match name.as_str() {
-                "main" => "_start".to_string(),
-                _ => name.clone(),
-            };
-            let mut statements = vec![];
+// System V AMD64 calling convention, the remaining arguments are passed on the stack
+const ARGUMENT_REGISTERS: [Param; 6] = [
+    Param::Rdi,
+    Param::Rsi,
+    Param::Rdx,
+    Param::Rcx,
+    Param::R8,
+    Param::R9,
+];
 
-            for child in node.children.iter() {
-                statements.append(&mut generate_operations(child));
+// The return address and the saved rbp sit between rbp and the stack arguments
+const STACK_ARGUMENTS_OFFSET: usize = 2 * QUADWORD_LENGTH;
+
+// rsp is a multiple of 16 at every call, so right after the return address and
+// the saved rbp are pushed it is again
+const STACK_ALIGNMENT: usize = 16;
+
+// A switch is left by break like a loop, but has no continue label of its own
+struct LoopLabels {
+    break_label: String,
+    continue_label: Option<String>,
+}
+
+// The labels of the case values of a switch, and of its default label
+struct SwitchLabels {
+    cases: Vec<(i64, String)>,
+    default_label: Option<String>,
+}
+
+struct GeneratorState {
+    label_count: usize,
+    loops: Vec<LoopLabels>,
+    switches: Vec<SwitchLabels>,
+    frame_size: usize, // grows with the temporaries of the current function
+    return_address: Option<usize>, // offset of the saved address for a struct returned in memory
+    strings: Vec<Vec<u8>>, // the distinct string literals, emitted into .rodata
+    stack_depth: usize, // bytes pushed below the frame of the current function
+    is_linked_with_libc: bool, // the C library provides the entry point that calls main
+}
+
+impl GeneratorState {
+    // Reserves stack space for a struct that is not stored in a variable, such as
+    // the value returned by a function call, and returns its offset
+    fn new_temporary(&mut self, ctype: &CType) -> usize {
+        self.frame_size += ctype.size().next_multiple_of(QUADWORD_LENGTH);
+        self.frame_size
+    }
+
+    // Identical string literals share their label
+    fn add_string(&mut self, bytes: &[u8]) -> String {
+        let index = match self.strings.iter().position(|string| string == bytes) {
+            Some(index) => index,
+            None => {
+                self.strings.push(bytes.to_vec());
+                self.strings.len() - 1
             }
+        };
+        get_string_label(index)
+    }
 
-            if matches!(node.children.last().unwrap().node_type, NodeType::Return) {
-                let split_statements = statements.split_last().unwrap();
+    // Values are pushed and popped through the state, which keeps track of how far
+    // rsp is below the frame so that calls can align it
+    fn push(&mut self, param: Param) -> Asm {
+        self.stack_depth += QUADWORD_LENGTH;
+        Asm::Push(param)
+    }
 
+    fn pop(&mut self, param: Param) -> Asm {
+        self.stack_depth -= QUADWORD_LENGTH;
+        Asm::Pop(param)
+    }
+
+    fn new_label(&mut self) -> String {
+        self.label_count += 1;
+        format!(".L{}", self.label_count)
+    }
+
+    fn get_break_label(&self, node: &ParseNode) -> Result<String, CompileError> {
+        match self.loops.last() {
+            Some(labels) => Ok(labels.break_label.clone()),
+            None => Err(CompileError::Codegen(
+                node.span.clone(),
+                "break statement not within loop or switch".to_string(),
+            )),
+        }
+    }
+
+    // Continue skips the switches between it and the innermost loop
+    fn get_continue_label(&self, node: &ParseNode) -> Result<String, CompileError> {
+        self.loops
+            .iter()
+            .rev()
+            .find_map(|labels| labels.continue_label.clone())
+            .ok_or_else(|| {
+                CompileError::Codegen(
+                    node.span.clone(),
+                    "continue statement not within a loop".to_string(),
+                )
+            })
+    }
+
+    // The label of the case with the value in the innermost switch, or of its
+    // default label without a value
+    fn get_case_label(&self, node: &ParseNode, value: Option<i64>) -> Result<String, CompileError> {
+        let labels = self.switches.last().ok_or_else(|| {
+            CompileError::Codegen(
+                node.span.clone(),
+                "case label not within a switch statement".to_string(),
+            )
+        })?;
+
+        let label = match value {
+            Some(value) => labels
+                .cases
+                .iter()
+                .find(|(case, _)| *case == value)
+                .map(|(_, label)| label),
+            None => labels.default_label.as_ref(),
+        };
+        Ok(label
+            .expect("Every label of a switch is collected before its body is generated")
+            .clone())
+    }
+}
+
+// Linux x86-64 syscall numbers
+const SYS_WRITE: i64 = 1;
+const SYS_EXIT: i64 = 60;
+
+fn generate_function_return() -> Vec<Asm> {
+    vec![Asm::Leave, Asm::Ret]
+}
+
+// Entry point of a binary without the C library, exits the process with the
+// return value of main
+fn generate_start_stub() -> Vec<Asm> {
+    vec![
+        Asm::Global("_start".to_string()),
+        Asm::FunctionRef("_start".to_string()),
+        Asm::Call("main".to_string()),
+        Asm::Mov(Param::Rdi, Param::Rax),
+        Asm::Mov(Param::Rax, Param::Constant(SYS_EXIT)),
+        Asm::Syscall,
+    ]
+}
+
+// Without the C library, a call to write that the program does not define is the
+// system call. Its arguments are already in the registers the system call takes
+// them in.
+fn generate_write_stub() -> Vec<Asm> {
+    vec![
+        Asm::FunctionRef("write".to_string()),
+        Asm::Mov(Param::Rax, Param::Constant(SYS_WRITE)),
+        Asm::Syscall,
+        Asm::Ret,
+    ]
+}
+
+// The register holding the lowest bytes of rax, to store a value of the given size
+fn get_accumulator(size: &Size) -> Param {
+    match size {
+        Size::Byte => Param::Al,
+        Size::Word => Param::Ax,
+        Size::Dword => Param::Eax,
+        Size::Qword => Param::Rax,
+    }
+}
+
+// The register holding the lowest bytes of rcx, which copies structs
+fn get_counter(size: &Size) -> Param {
+    match size {
+        Size::Byte => Param::Cl,
+        Size::Word => Param::Cx,
+        Size::Dword => Param::Ecx,
+        Size::Qword => Param::Rcx,
+    }
+}
+
+// Splits the bytes of an object into the largest pieces that can be moved at once,
+// from its lowest byte upwards
+fn get_pieces(size: usize) -> Vec<(i64, Size)> {
+    let mut pieces = vec![];
+    let mut offset = 0;
+
+    while offset < size {
+        let piece = match size - offset {
+            1 => Size::Byte,
+            2 | 3 => Size::Word,
+            4..=7 => Size::Dword,
+            _ => Size::Qword,
+        };
+        pieces.push((offset as i64, piece.clone()));
+        offset += piece.bytes();
+    }
+
+    pieces
+}
+
+// Copies size bytes between two memory operands through rcx
+fn generate_copy(destination: &Param, source: &Param, size: usize) -> Vec<Asm> {
+    get_pieces(size)
+        .into_iter()
+        .flat_map(|(offset, piece)| {
+            vec![
+                Asm::Mov(get_counter(&piece), source.offset(offset, piece.clone())),
+                Asm::Mov(
+                    destination.offset(offset, piece.clone()),
+                    get_counter(&piece),
+                ),
+            ]
+        })
+        .collect()
+}
+
+// Loads an eightbyte of a struct into the register, which is not rcx. The last
+// eightbyte of a struct may be shorter, so it is put together from its pieces
+// without reading past the end of the struct.
+fn generate_eightbyte_load(register: Param, source: &Param, size: usize) -> Vec<Asm> {
+    let mut operations = vec![];
+
+    for (i, (offset, piece)) in get_pieces(size).into_iter().rev().enumerate() {
+        if i > 0 {
+            operations.push(Asm::Shl(
+                register.clone(),
+                Param::Constant(8 * piece.bytes() as i64),
+            ));
+        }
+
+        operations.push(match piece {
+            Size::Dword => Asm::Mov(Param::Ecx, source.offset(offset, piece)),
+            Size::Qword => Asm::Mov(Param::Rcx, source.offset(offset, piece)),
+            _ => Asm::Movzx(Param::Rcx, source.offset(offset, piece)),
+        });
+        operations.push(if i > 0 {
+            Asm::Or(register.clone(), Param::Rcx)
+        } else {
+            Asm::Mov(register.clone(), Param::Rcx)
+        });
+    }
+
+    operations
+}
+
+// Stores the lowest size bytes of rax, an eightbyte of a struct, to memory
+fn generate_eightbyte_store(destination: &Param, size: usize) -> Vec<Asm> {
+    let mut operations = vec![];
+
+    let pieces = get_pieces(size);
+    let piece_count = pieces.len();
+
+    for (i, (offset, piece)) in pieces.into_iter().enumerate() {
+        operations.push(Asm::Mov(
+            destination.offset(offset, piece.clone()),
+            get_accumulator(&piece),
+        ));
+
+        if i + 1 < piece_count {
+            operations.push(Asm::Shr(
+                Param::Rax,
+                Param::Constant(8 * piece.bytes() as i64),
+            ));
+        }
+    }
+
+    operations
+}
+
+// Values are kept in rax sign or zero extended to 64 bits, following the
+// signedness of their type. A struct is kept as its address instead.
+fn generate_load(source: Param, ctype: &CType) -> Vec<Asm> {
+    vec![match ctype {
+        CType::Bool | CType::UnsignedChar | CType::UnsignedShort => Asm::Movzx(Param::Rax, source),
+        CType::Char | CType::Short => Asm::Movsx(Param::Rax, source),
+        CType::Int => Asm::Movsxd(Param::Rax, source),
+        // Writing a 32 bit register clears the upper half of the 64 bit register
+        CType::UnsignedInt => Asm::Mov(Param::Eax, source),
+        CType::Long
+        | CType::UnsignedLong
+        | CType::LongLong
+        | CType::UnsignedLongLong
+        | CType::Pointer(_) => Asm::Mov(Param::Rax, source),
+        CType::Array(_, _) | CType::UnevaluatedArray(_, _) => {
+            panic!("Arrays are converted to pointers before they are loaded")
+        }
+        CType::Struct(_) => Asm::Lea(Param::Rax, source),
+        CType::Void => panic!("void has no values to load"),
+        CType::Const(_) => panic!("Values are loaded with their unqualified type"),
+    }]
+}
+
+// Stores the value in rax, a struct is copied from the address in rax
+fn generate_store(target: Param, ctype: &CType) -> Vec<Asm> {
+    match ctype {
+        CType::Struct(_) => generate_copy(
+            &target,
+            &Param::memory(Size::Qword, Param::Rax, 0),
+            ctype.size(),
+        ),
+        _ => vec![Asm::Mov(target, get_accumulator(&Size::of(ctype)))],
+    }
+}
+
+// Wraps the value in rax around to the range of the type, by extending its lowest
+// bytes again. Converting to _Bool compares with 0 instead.
+fn generate_truncation(ctype: &CType) -> Vec<Asm> {
+    match ctype {
+        CType::Bool => generate_set_condition(Condition::NotEqual, Param::Constant(0)),
+        CType::Char => vec![Asm::Movsx(Param::Rax, Param::Al)],
+        CType::UnsignedChar => vec![Asm::Movzx(Param::Rax, Param::Al)],
+        CType::Short => vec![Asm::Movsx(Param::Rax, Param::Ax)],
+        CType::UnsignedShort => vec![Asm::Movzx(Param::Rax, Param::Ax)],
+        CType::Int => vec![Asm::Movsxd(Param::Rax, Param::Eax)],
+        CType::UnsignedInt => vec![Asm::Mov(Param::Eax, Param::Eax)],
+        CType::Long
+        | CType::UnsignedLong
+        | CType::LongLong
+        | CType::UnsignedLongLong
+        | CType::Pointer(_) => vec![],
+        CType::Array(_, _) | CType::Struct(_) | CType::UnevaluatedArray(_, _) | CType::Void => {
+            panic!("Only scalars are converted")
+        }
+        CType::Const(_) => panic!("Values are converted to unqualified types"),
+    }
+}
+
+// Integer conversions, C 6.3.1.3: a value that fits in the new type stays the same.
+// A value converted to void is discarded.
+fn generate_conversion(from: &CType, to: &CType) -> Vec<Asm> {
+    if to.is_void() || from.fits_in(to) {
+        vec![]
+    } else {
+        generate_truncation(to)
+    }
+}
+
+// Where an argument is passed. The eightbytes of a struct of at most 16 bytes
+// are passed like integers, in consecutive registers, larger structs in memory.
+enum ArgumentLocation {
+    Registers(usize), // index of the register of the first eightbyte
+    Stack(usize),     // offset from the first stack argument
+}
+
+// A struct larger than 16 bytes is returned in memory provided by the caller,
+// whose address is passed in rdi as a hidden first argument
+fn is_returned_in_memory(ctype: &CType) -> bool {
+    ctype.is_struct() && ctype.size() > 2 * QUADWORD_LENGTH
+}
+
+fn get_eightbyte_count(ctype: &CType) -> usize {
+    ctype.size().div_ceil(QUADWORD_LENGTH)
+}
+
+// The size of the eightbyte of a struct at the index, only the last one may be shorter
+fn get_eightbyte_size(ctype: &CType, index: usize) -> usize {
+    (ctype.size() - index * QUADWORD_LENGTH).min(QUADWORD_LENGTH)
+}
+
+// Hands out the argument registers in order. An argument that does not fit in
+// the remaining registers goes on the stack, while later arguments may still
+// take the remaining registers. Also returns the size of the stack arguments.
+fn classify_arguments(types: &[CType], return_type: &CType) -> (Vec<ArgumentLocation>, usize) {
+    let mut register = usize::from(is_returned_in_memory(return_type));
+    let mut stack_size = 0;
+
+    let locations = types
+        .iter()
+        .map(|ctype| {
+            let eightbytes = get_eightbyte_count(ctype);
+
+            if !is_returned_in_memory(ctype) && register + eightbytes <= ARGUMENT_REGISTERS.len() {
+                register += eightbytes;
+                ArgumentLocation::Registers(register - eightbytes)
+            } else {
+                stack_size += ctype.size().next_multiple_of(QUADWORD_LENGTH);
+                ArgumentLocation::Stack(stack_size - ctype.size().next_multiple_of(QUADWORD_LENGTH))
+            }
+        })
+        .collect();
+
+    (locations, stack_size)
+}
+
+// Arguments arrive in 64 bit registers or stack slots, and are stored with the
+// size of their parameter. A struct arrives an eightbyte per register, or as a
+// copy on the stack.
+fn generate_param_store(location: ArgumentLocation, offset: usize, ctype: &CType) -> Vec<Asm> {
+    let target = Param::local(offset, ctype);
+
+    match location {
+        ArgumentLocation::Registers(index) if ctype.is_struct() => (0..get_eightbyte_count(ctype))
+            .flat_map(|i| {
                 join_asm!(
-                    Asm::FunctionRef(function_name.clone()),
-                    Asm::Push(Param::Rbp),
-                    Asm::Mov(Param::Rbp, Param::Rsp),
-                    Asm::Sub(
-                        Param::Rsp,
-                        Param::Constant((n_variables * QUADWORD_LENGTH) as i32)
-                    ),
-                    split_statements.1,
-                    Asm::Add(
-                        Param::Rsp,
-                        Param::Constant((n_variables * QUADWORD_LENGTH) as i32)
-                    ),
-                    Asm::Pop(Param::Rbp),
-                    split_statements.0
+                    Asm::Mov(Param::Rax, ARGUMENT_REGISTERS[index + i].clone()),
+                    generate_eightbyte_store(
+                        &target.offset((i * QUADWORD_LENGTH) as i64, Size::Qword),
+                        get_eightbyte_size(ctype, i)
+                    )
                 )
+            })
+            .collect(),
+        ArgumentLocation::Registers(index) => join_asm!(
+            Asm::Mov(Param::Rax, ARGUMENT_REGISTERS[index].clone()),
+            generate_store(target, ctype)
+        ),
+        ArgumentLocation::Stack(stack_offset) => {
+            let argument = Param::memory(
+                Size::Qword,
+                Param::Rbp,
+                (STACK_ARGUMENTS_OFFSET + stack_offset) as i64,
+            );
+
+            if ctype.is_struct() {
+                generate_copy(&target, &argument, ctype.size())
             } else {
                 join_asm!(
-                    Asm::FunctionRef(function_name.clone()),
-                    Asm::Push(Param::Rbp),
-                    Asm::Mov(Param::Rbp, Param::Rsp),
-                    Asm::Sub(
-                        Param::Rsp,
-                        Param::Constant((n_variables * QUADWORD_LENGTH) as i32)
-                    ),
-                    statements,
-                    Asm::Add(
-                        Param::Rsp,
-                        Param::Constant((n_variables * QUADWORD_LENGTH) as i32)
-                    ),
-                    Asm::Pop(Param::Rbp)
+                    Asm::Mov(Param::Rax, argument),
+                    generate_store(target, ctype)
                 )
             }
         }
-        NodeType::Return => join_asm!(
-            generate_operations(node.get_child(0)),
-            Asm::Mov(Param::Rbx, Param::Rax),
-            Asm::Mov(Param::Rax, Param::Constant(1)),
-            Asm::OSInterrupt
+    }
+}
+
+// The stack arguments are stored above rsp first, which is lowered further if
+// needed to align it. The register arguments are pushed right to left an eightbyte
+// at a time, so they can be popped into their registers in order. A variadic
+// function gets the number of vector registers it is passed in al, always 0.
+fn generate_function_call(
+    node: &ParseNode,
+    name: &str,
+    return_type: &CType,
+    is_variadic: bool,
+    state: &mut GeneratorState,
+) -> Result<Vec<Asm>, CompileError> {
+    let types: Vec<CType> = node.children.iter().map(ParseNode::get_type).collect();
+    let (locations, stack_size) = classify_arguments(&types, return_type);
+    let stack_size = stack_size + (state.stack_depth + stack_size) % STACK_ALIGNMENT;
+    let mut operations = vec![];
+
+    if stack_size > 0 {
+        operations.push(Asm::Sub(Param::Rsp, Param::Constant(stack_size as i64)));
+        state.stack_depth += stack_size;
+    }
+
+    for ((argument, ctype), location) in node.children.iter().zip(&types).zip(&locations) {
+        if let ArgumentLocation::Stack(offset) = location {
+            operations.append(&mut generate_node_operations(argument, state)?);
+            operations.append(&mut generate_store(
+                Param::memory(Size::of(ctype), Param::Rsp, *offset as i64),
+                ctype,
+            ));
+        }
+    }
+
+    let mut register_count = 0;
+    for ((argument, ctype), location) in node.children.iter().zip(&types).zip(&locations).rev() {
+        if let ArgumentLocation::Registers(_) = location {
+            operations.append(&mut generate_node_operations(argument, state)?);
+
+            if ctype.is_struct() {
+                operations.push(Asm::Mov(Param::R10, Param::Rax));
+                for i in (0..get_eightbyte_count(ctype)).rev() {
+                    operations.append(&mut generate_eightbyte_load(
+                        Param::Rax,
+                        &Param::memory(Size::Qword, Param::R10, (i * QUADWORD_LENGTH) as i64),
+                        get_eightbyte_size(ctype, i),
+                    ));
+                    operations.push(state.push(Param::Rax));
+                }
+            } else {
+                operations.push(state.push(Param::Rax));
+            }
+
+            register_count += get_eightbyte_count(ctype);
+        }
+    }
+
+    let first_register = usize::from(is_returned_in_memory(return_type));
+    for register in &ARGUMENT_REGISTERS[first_register..first_register + register_count] {
+        operations.push(state.pop(register.clone()));
+    }
+
+    // A struct is returned into a temporary, and its address is the value of the call
+    let temporary = return_type.is_struct().then(|| {
+        Param::memory(
+            Size::Qword,
+            Param::Rbp,
+            -(state.new_temporary(return_type) as i64),
+        )
+    });
+
+    match &temporary {
+        Some(temporary) if is_returned_in_memory(return_type) => {
+            operations.push(Asm::Lea(Param::Rdi, temporary.clone()))
+        }
+        _ => {}
+    }
+
+    if is_variadic {
+        operations.push(Asm::Mov(Param::Eax, Param::Constant(0)));
+    }
+    operations.push(Asm::Call(name.to_string()));
+
+    if stack_size > 0 {
+        operations.push(Asm::Add(Param::Rsp, Param::Constant(stack_size as i64)));
+        state.stack_depth -= stack_size;
+    }
+
+    if let Some(temporary) = temporary {
+        if !is_returned_in_memory(return_type) {
+            operations.push(Asm::Mov(temporary.clone(), Param::Rax));
+            if return_type.size() > QUADWORD_LENGTH {
+                operations.push(Asm::Mov(
+                    temporary.offset(QUADWORD_LENGTH as i64, Size::Qword),
+                    Param::Rdx,
+                ));
+            }
+        }
+        operations.push(Asm::Lea(Param::Rax, temporary));
+    }
+
+    Ok(operations)
+}
+
+// A struct is returned in memory at the address the caller passed, which is also
+// returned in rax, or otherwise in rax and rdx
+fn generate_return_value(ctype: &CType, state: &GeneratorState) -> Vec<Asm> {
+    if !ctype.is_struct() {
+        return vec![];
+    }
+
+    match state.return_address {
+        Some(offset) => join_asm!(
+            Asm::Mov(
+                Param::Rdx,
+                Param::memory(Size::Qword, Param::Rbp, -(offset as i64))
+            ),
+            generate_copy(
+                &Param::memory(Size::Qword, Param::Rdx, 0),
+                &Param::memory(Size::Qword, Param::Rax, 0),
+                ctype.size()
+            ),
+            Asm::Mov(Param::Rax, Param::Rdx)
         ),
-        NodeType::VarDecl(_, offset) => {
-            join_asm!(
-                generate_operations(node.get_child(0)),
-                Asm::Mov(Param::BpMinus(*offset as usize), Param::Rax)
+        None => join_asm!(
+            Asm::Mov(Param::R10, Param::Rax),
+            if ctype.size() > QUADWORD_LENGTH {
+                generate_eightbyte_load(
+                    Param::Rdx,
+                    &Param::memory(Size::Qword, Param::R10, QUADWORD_LENGTH as i64),
+                    get_eightbyte_size(ctype, 1),
+                )
+            } else {
+                vec![]
+            },
+            generate_eightbyte_load(
+                Param::Rax,
+                &Param::memory(Size::Qword, Param::R10, 0),
+                get_eightbyte_size(ctype, 0)
             )
+        ),
+    }
+}
+
+// Falls through if the expression is non-zero, otherwise jumps to the label
+fn generate_jump_if_zero(
+    node: &ParseNode,
+    label: &str,
+    state: &mut GeneratorState,
+) -> Result<Vec<Asm>, CompileError> {
+    Ok(match node.node_type {
+        NodeType::Empty => vec![],
+        _ => join_asm!(
+            generate_node_operations(node, state)?,
+            Asm::Cmp(Param::Rax, Param::Constant(0)),
+            Asm::Jcc(Condition::Equal, label.to_string())
+        ),
+    })
+}
+
+fn generate_loop_body(
+    node: &ParseNode,
+    labels: LoopLabels,
+    state: &mut GeneratorState,
+) -> Result<Vec<Asm>, CompileError> {
+    state.loops.push(labels);
+    let body = generate_node_operations(node, state);
+    state.loops.pop();
+
+    body
+}
+
+fn generate_if(node: &ParseNode, state: &mut GeneratorState) -> Result<Vec<Asm>, CompileError> {
+    let else_label = state.new_label();
+    let end_label = state.new_label();
+
+    let else_operations = match node.children.get(2) {
+        Some(else_node) => generate_node_operations(else_node, state)?,
+        None => vec![],
+    };
+
+    Ok(join_asm!(
+        generate_jump_if_zero(node.get_child(0), &else_label, state)?,
+        generate_node_operations(node.get_child(1), state)?,
+        Asm::Jmp(end_label.clone()),
+        Asm::Label(else_label),
+        else_operations,
+        Asm::Label(end_label)
+    ))
+}
+
+fn generate_while(node: &ParseNode, state: &mut GeneratorState) -> Result<Vec<Asm>, CompileError> {
+    let start_label = state.new_label();
+    let end_label = state.new_label();
+    let labels = LoopLabels {
+        break_label: end_label.clone(),
+        continue_label: Some(start_label.clone()),
+    };
+
+    Ok(join_asm!(
+        Asm::Label(start_label.clone()),
+        generate_jump_if_zero(node.get_child(0), &end_label, state)?,
+        generate_loop_body(node.get_child(1), labels, state)?,
+        Asm::Jmp(start_label),
+        Asm::Label(end_label)
+    ))
+}
+
+fn generate_do_while(
+    node: &ParseNode,
+    state: &mut GeneratorState,
+) -> Result<Vec<Asm>, CompileError> {
+    let start_label = state.new_label();
+    let continue_label = state.new_label();
+    let end_label = state.new_label();
+    let labels = LoopLabels {
+        break_label: end_label.clone(),
+        continue_label: Some(continue_label.clone()),
+    };
+
+    Ok(join_asm!(
+        Asm::Label(start_label.clone()),
+        generate_loop_body(node.get_child(0), labels, state)?,
+        Asm::Label(continue_label),
+        generate_node_operations(node.get_child(1), state)?,
+        Asm::Cmp(Param::Rax, Param::Constant(0)),
+        Asm::Jcc(Condition::NotEqual, start_label),
+        Asm::Label(end_label)
+    ))
+}
+
+fn generate_for(node: &ParseNode, state: &mut GeneratorState) -> Result<Vec<Asm>, CompileError> {
+    let start_label = state.new_label();
+    let continue_label = state.new_label();
+    let end_label = state.new_label();
+    let labels = LoopLabels {
+        break_label: end_label.clone(),
+        continue_label: Some(continue_label.clone()),
+    };
+
+    Ok(join_asm!(
+        generate_node_operations(node.get_child(0), state)?,
+        Asm::Label(start_label.clone()),
+        generate_jump_if_zero(node.get_child(1), &end_label, state)?,
+        generate_loop_body(node.get_child(3), labels, state)?,
+        Asm::Label(continue_label),
+        generate_node_operations(node.get_child(2), state)?,
+        Asm::Jmp(start_label),
+        Asm::Label(end_label)
+    ))
+}
+
+// The case values of a switch in the order they appear, and whether it has a
+// default label. The labels of a nested switch belong to that switch.
+fn get_case_values(node: &ParseNode, values: &mut Vec<i64>, has_default: &mut bool) {
+    match &node.node_type {
+        NodeType::Switch => return,
+        NodeType::Case => match node.get_child(0).node_type {
+            NodeType::Const(value, _) => values.push(value),
+            _ => panic!("{:?} has no constant value", node),
+        },
+        NodeType::Default => *has_default = true,
+        _ => (),
+    }
+
+    for child in node.children.iter() {
+        get_case_values(child, values, has_default);
+    }
+}
+
+// A jump table is used for at least this many cases, when it has at most this
+// many entries per case. The entries between the cases jump to default.
+const JUMP_TABLE_MIN_CASES: usize = 4;
+const JUMP_TABLE_MAX_ENTRIES_PER_CASE: i64 = 3;
+
+// The range of the case values, if they are dense enough for a jump table. The
+// smallest value is subtracted with an immediate, which is 32 bits.
+fn get_jump_table_range(values: &[i64]) -> Option<(i64, i64)> {
+    let min = *values.iter().min()?;
+    let max = *values.iter().max()?;
+    let entries = max.checked_sub(min)?.checked_add(1)?;
+
+    let is_dense = values.len() >= JUMP_TABLE_MIN_CASES
+        && entries <= values.len() as i64 * JUMP_TABLE_MAX_ENTRIES_PER_CASE;
+    (is_dense && i32::try_from(min).is_ok()).then_some((min, max))
+}
+
+// Jumps to the label of the value in rax through the table of the labels of the
+// values from min to max, which is put in .rodata. The subtraction moves the
+// values below min above the table, so one unsigned comparison checks both ends.
+fn generate_jump_table(
+    labels: &SwitchLabels,
+    (min, max): (i64, i64),
+    fallback_label: &str,
+    state: &mut GeneratorState,
+) -> Vec<Asm> {
+    let table_label = state.new_label();
+    let entries: Vec<&str> = (min..=max)
+        .map(|value| {
+            labels
+                .cases
+                .iter()
+                .find(|(case, _)| *case == value)
+                .map_or(fallback_label, |(_, label)| label)
+        })
+        .collect();
+
+    vec![
+        Asm::Sub(Param::Rax, Param::Constant(min)),
+        Asm::Cmp(Param::Rax, Param::Constant(max - min)),
+        Asm::Jcc(Condition::Above, fallback_label.to_string()),
+        Asm::Lea(
+            Param::Rcx,
+            Param::memory(Size::Qword, Param::RipRelative(table_label.clone()), 0),
+        ),
+        Asm::JmpIndirect(Param::Memory(Box::new(Memory {
+            size: Size::Qword,
+            base: Param::Rcx,
+            index: Some((Param::Rax, QUADWORD_LENGTH)),
+            displacement: 0,
+        }))),
+        Asm::Section(Section::Rodata),
+        Asm::Align(QUADWORD_LENGTH),
+        Asm::Label(table_label),
+        Asm::Define(Size::Qword, entries.join(", ")),
+        Asm::Section(Section::Text),
+    ]
+}
+
+// Compares the value in rax with every case value in turn. A value that does not
+// fit in the 32 bit immediate of cmp is compared through rcx.
+fn generate_compare_chain(labels: &SwitchLabels, fallback_label: &str) -> Vec<Asm> {
+    let mut operations = vec![];
+
+    for (value, label) in labels.cases.iter() {
+        if i32::try_from(*value).is_ok() {
+            operations.push(Asm::Cmp(Param::Rax, Param::Constant(*value)));
+        } else {
+            operations.push(Asm::Mov(Param::Rcx, Param::Constant(*value)));
+            operations.push(Asm::Cmp(Param::Rax, Param::Rcx));
         }
-        NodeType::Var(_, offset) => {
-            vec![Asm::Mov(Param::Rax, Param::BpMinus(*offset as usize))]
+        operations.push(Asm::Jcc(Condition::Equal, label.clone()));
+    }
+    operations.push(Asm::Jmp(fallback_label.to_string()));
+
+    operations
+}
+
+// Jumps to the case label of the value of the controlling expression, or to the
+// default label or the end of the switch when no case matches. The statements
+// of the body then run on from the label, until a break leaves the switch.
+fn generate_switch(node: &ParseNode, state: &mut GeneratorState) -> Result<Vec<Asm>, CompileError> {
+    let end_label = state.new_label();
+    let (mut values, mut has_default) = (vec![], false);
+    get_case_values(node.get_child(1), &mut values, &mut has_default);
+
+    let labels = SwitchLabels {
+        cases: values
+            .iter()
+            .map(|value| (*value, state.new_label()))
+            .collect(),
+        default_label: has_default.then(|| state.new_label()),
+    };
+    let fallback_label = labels.default_label.clone().unwrap_or(end_label.clone());
+
+    let value = generate_node_operations(node.get_child(0), state)?;
+    let jump = match get_jump_table_range(&values) {
+        Some(range) => generate_jump_table(&labels, range, &fallback_label, state),
+        None => generate_compare_chain(&labels, &fallback_label),
+    };
+
+    state.switches.push(labels);
+    state.loops.push(LoopLabels {
+        break_label: end_label.clone(),
+        continue_label: None,
+    });
+    let body = generate_node_operations(node.get_child(1), state);
+    state.loops.pop();
+    state.switches.pop();
+
+    Ok(join_asm!(value, jump, body?, Asm::Label(end_label)))
+}
+
+// Leaves the left operand in rax and the right operand in rcx
+fn generate_binary_operands(
+    node: &ParseNode,
+    state: &mut GeneratorState,
+) -> Result<Vec<Asm>, CompileError> {
+    Ok(join_asm!(
+        generate_node_operations(node.get_child(1), state)?,
+        state.push(Param::Rax),
+        generate_node_operations(node.get_child(0), state)?,
+        state.pop(Param::Rcx)
+    ))
+}
+
+// Compares rax with the operand and stores the condition as 0 or 1 in rax
+fn generate_set_condition(condition: Condition, operand: Param) -> Vec<Asm> {
+    vec![
+        Asm::Cmp(Param::Rax, operand),
+        Asm::Set(condition, Param::Al),
+        Asm::Movzx(Param::Rax, Param::Al),
+    ]
+}
+
+fn generate_unary_operation(operation: &UnaryOp, ctype: &CType) -> Vec<Asm> {
+    match operation {
+        UnaryOp::Plus => vec![],
+        UnaryOp::Minus => join_asm!(Asm::Neg(Param::Rax), generate_truncation(ctype)),
+        UnaryOp::BitwiseNot => join_asm!(Asm::Not(Param::Rax), generate_truncation(ctype)),
+        UnaryOp::LogicalNot => generate_set_condition(Condition::Equal, Param::Constant(0)),
+        UnaryOp::AddressOf | UnaryOp::Dereference => {
+            panic!("Address operations work on lvalues and are generated separately")
         }
-        NodeType::Const(x) => {
-            vec![Asm::Mov(Param::Rax, Param::Constant(*x))]
+    }
+}
+
+// Performs the operation in the given type on the operands in rax and rcx. The
+// bitwise operations keep the result within the range of the type.
+fn generate_binary_operation(operation: &BinaryOp, ctype: &CType) -> Vec<Asm> {
+    let is_signed = ctype.is_signed();
+    let is_dword = ctype.size() == 4;
+
+    match operation {
+        BinaryOp::Plus => join_asm!(Asm::Add(Param::Rax, Param::Rcx), generate_truncation(ctype)),
+        BinaryOp::Minus => join_asm!(Asm::Sub(Param::Rax, Param::Rcx), generate_truncation(ctype)),
+        BinaryOp::Multiplication => {
+            join_asm!(Asm::Mul(Param::Rax, Param::Rcx), generate_truncation(ctype))
+        }
+        // Dividing ints with the 32-bit instruction faults on INT_MIN / -1 like gcc,
+        // instead of giving a quotient that doesn't fit
+        BinaryOp::Division | BinaryOp::Modulo if is_dword => join_asm!(
+            if is_signed {
+                vec![Asm::Cdq, Asm::Idiv(Param::Ecx)]
+            } else {
+                vec![Asm::Xor(Param::Edx, Param::Edx), Asm::Div(Param::Ecx)]
+            },
+            if *operation == BinaryOp::Modulo {
+                vec![Asm::Mov(Param::Eax, Param::Edx)]
+            } else {
+                vec![]
+            },
+            generate_truncation(ctype)
+        ),
+        BinaryOp::Division | BinaryOp::Modulo => join_asm!(
+            if is_signed {
+                vec![Asm::Cqo, Asm::Idiv(Param::Rcx)]
+            } else {
+                vec![Asm::Xor(Param::Rdx, Param::Rdx), Asm::Div(Param::Rcx)]
+            },
+            if *operation == BinaryOp::Modulo {
+                vec![Asm::Mov(Param::Rax, Param::Rdx)]
+            } else {
+                vec![]
+            }
+        ),
+        BinaryOp::Less if is_signed => generate_set_condition(Condition::Less, Param::Rcx),
+        BinaryOp::Less => generate_set_condition(Condition::Below, Param::Rcx),
+        BinaryOp::LessOrEqual if is_signed => {
+            generate_set_condition(Condition::LessOrEqual, Param::Rcx)
+        }
+        BinaryOp::LessOrEqual => generate_set_condition(Condition::BelowOrEqual, Param::Rcx),
+        BinaryOp::Greater if is_signed => generate_set_condition(Condition::Greater, Param::Rcx),
+        BinaryOp::Greater => generate_set_condition(Condition::Above, Param::Rcx),
+        BinaryOp::GreaterOrEqual if is_signed => {
+            generate_set_condition(Condition::GreaterOrEqual, Param::Rcx)
+        }
+        BinaryOp::GreaterOrEqual => generate_set_condition(Condition::AboveOrEqual, Param::Rcx),
+        BinaryOp::Equal => generate_set_condition(Condition::Equal, Param::Rcx),
+        BinaryOp::NotEqual => generate_set_condition(Condition::NotEqual, Param::Rcx),
+        BinaryOp::BitwiseAnd => vec![Asm::And(Param::Rax, Param::Rcx)],
+        BinaryOp::BitwiseOr => vec![Asm::Or(Param::Rax, Param::Rcx)],
+        BinaryOp::BitwiseXor => vec![Asm::Xor(Param::Rax, Param::Rcx)],
+        BinaryOp::ShiftLeft => {
+            join_asm!(Asm::Shl(Param::Rax, Param::Cl), generate_truncation(ctype))
+        }
+        BinaryOp::ShiftRight if is_signed => vec![Asm::Sar(Param::Rax, Param::Cl)],
+        BinaryOp::ShiftRight => vec![Asm::Shr(Param::Rax, Param::Cl)],
+        BinaryOp::LogicalAnd | BinaryOp::LogicalOr => {
+            panic!("Logical operations short-circuit and are generated separately")
+        }
+    }
+}
+
+// Divides by a constant divisor with a multiplication where it can, which is many
+// times faster than the division instruction
+fn generate_division(
+    node: &ParseNode,
+    operation: &BinaryOp,
+    state: &mut GeneratorState,
+) -> Result<Vec<Asm>, CompileError> {
+    let ctype = node.get_child(0).get_type();
+    let constant = get_constant_operand(node.get_child(1))
+        .and_then(|divisor| Some((divisor, generate_constant_quotient(divisor, &ctype)?)));
+
+    let Some((divisor, quotient)) = constant else {
+        return Ok(join_asm!(
+            generate_binary_operands(node, state)?,
+            generate_binary_operation(operation, &ctype)
+        ));
+    };
+
+    // The remainder is what is left of the dividend in rcx after taking away the
+    // quotient times the divisor
+    let remainder = match operation {
+        BinaryOp::Modulo => vec![
+            Asm::Mov(Param::Rdx, Param::Constant(divisor)),
+            Asm::Mul(Param::Rax, Param::Rdx),
+            Asm::Sub(Param::Rcx, Param::Rax),
+            Asm::Mov(Param::Rax, Param::Rcx),
+        ],
+        _ => vec![],
+    };
+
+    Ok(join_asm!(
+        generate_node_operations(node.get_child(0), state)?,
+        quotient,
+        generate_truncation(&ctype),
+        remainder
+    ))
+}
+
+// The value of an operand that is a constant, possibly converted to the type of
+// the operation
+fn get_constant_operand(node: &ParseNode) -> Option<i64> {
+    match &node.node_type {
+        NodeType::Const(value, _) => Some(*value),
+        NodeType::Cast(ctype) => {
+            get_constant_operand(node.get_child(0)).map(|value| ctype.truncate(value))
+        }
+        NodeType::UnaryOp(UnaryOp::Minus) => match &node.get_child(0).node_type {
+            NodeType::Const(value, ctype) => Some(ctype.truncate(value.wrapping_neg())),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Divides rax by the divisor, rounding towards zero, by multiplying with its
+// reciprocal as a fixed point number, following Granlund and Montgomery, "Division
+// by Invariant Integers using Multiplication". The dividend is kept in rcx. Gives
+// None for divisors that are left to the division instruction.
+fn generate_constant_quotient(divisor: i64, ctype: &CType) -> Option<Vec<Asm>> {
+    let bits = 8 * ctype.size() as u32;
+
+    if ctype.is_signed() {
+        generate_signed_quotient(divisor, bits)
+    } else {
+        generate_unsigned_quotient(divisor as u64, bits)
+    }
+}
+
+fn generate_unsigned_quotient(divisor: u64, bits: u32) -> Option<Vec<Asm>> {
+    if divisor == 0 {
+        return None;
+    }
+
+    let mut operations = vec![Asm::Mov(Param::Rcx, Param::Rax)];
+    if divisor.is_power_of_two() {
+        let shift = divisor.trailing_zeros();
+        if shift > 0 {
+            operations.push(Asm::Shr(Param::Rax, Param::Constant(shift as i64)));
+        }
+        return Some(operations);
+    }
+
+    // With l = ceil(log2(d)), the reciprocal is 2^(N + l) / d rounded up, less the
+    // 2^N that doesn't fit, which is added back as the dividend
+    let log = 64 - (divisor - 1).leading_zeros();
+    let magic = (1u128 << bits) * ((1u128 << log) - divisor as u128) / divisor as u128 + 1;
+
+    // The high half of magic * dividend goes to rdx
+    if bits == 64 {
+        operations.extend([
+            Asm::Mov(Param::Rax, Param::Constant(magic as i64)),
+            Asm::MulWide(Param::Rcx),
+        ]);
+    } else {
+        operations.extend([
+            Asm::Mov(Param::Rdx, Param::Constant(magic as i64)),
+            Asm::Mul(Param::Rdx, Param::Rcx),
+            Asm::Shr(Param::Rdx, Param::Constant(bits as i64)),
+        ]);
+    }
+
+    // (high + (dividend - high) / 2) >> (l - 1) adds the dividend without overflowing
+    operations.extend([
+        Asm::Mov(Param::Rax, Param::Rcx),
+        Asm::Sub(Param::Rax, Param::Rdx),
+        Asm::Shr(Param::Rax, Param::Constant(1)),
+        Asm::Add(Param::Rax, Param::Rdx),
+    ]);
+    if log > 1 {
+        operations.push(Asm::Shr(Param::Rax, Param::Constant(log as i64 - 1)));
+    }
+
+    Some(operations)
+}
+
+fn generate_signed_quotient(divisor: i64, bits: u32) -> Option<Vec<Asm>> {
+    if divisor == 0 || divisor == i64::MIN >> (64 - bits) {
+        return None;
+    }
+
+    let magnitude = divisor.unsigned_abs();
+    let mut operations = vec![Asm::Mov(Param::Rcx, Param::Rax)];
+
+    if magnitude.is_power_of_two() && magnitude > 1 {
+        // Negative dividends are biased by 2^k - 1 so that the shift rounds them
+        // towards zero rather than down
+        let shift = magnitude.trailing_zeros() as i64;
+        operations.extend([
+            Asm::Sar(Param::Rax, Param::Constant(63)),
+            Asm::Shr(Param::Rax, Param::Constant(64 - shift)),
+            Asm::Add(Param::Rax, Param::Rcx),
+            Asm::Sar(Param::Rax, Param::Constant(shift)),
+        ]);
+    } else if magnitude > 1 {
+        // With l = ceil(log2(|d|)), the reciprocal is 2^(N + l - 1) / |d| rounded up.
+        // It takes N bits, so as a 64-bit multiplier it is negative and the dividend
+        // is added back to the high half.
+        let log = 64 - (magnitude - 1).leading_zeros();
+        let magic = (1u128 << (bits + log - 1)) / magnitude as u128 + 1;
+
+        if bits == 64 {
+            operations.extend([
+                Asm::Mov(Param::Rax, Param::Constant(magic as i64)),
+                Asm::ImulWide(Param::Rcx),
+                Asm::Add(Param::Rdx, Param::Rcx),
+                Asm::Sar(Param::Rdx, Param::Constant(log as i64 - 1)),
+            ]);
+        } else {
+            operations.extend([
+                Asm::Mov(Param::Rdx, Param::Constant(magic as i64)),
+                Asm::Mul(Param::Rdx, Param::Rcx),
+                Asm::Sar(Param::Rdx, Param::Constant((bits + log - 1) as i64)),
+            ]);
+        }
+
+        // The shift rounds down, so negative dividends get one added
+        operations.extend([
+            Asm::Mov(Param::Rax, Param::Rcx),
+            Asm::Shr(Param::Rax, Param::Constant(63)),
+            Asm::Add(Param::Rax, Param::Rdx),
+        ]);
+    }
+
+    if divisor < 0 {
+        operations.push(Asm::Neg(Param::Rax));
+    }
+
+    Some(operations)
+}
+
+// Short-circuits && and ||: the right operand is skipped when the left operand
+// already decides the outcome, which is when rax meets the skip condition
+fn generate_logical_operation(
+    node: &ParseNode,
+    skip_condition: Condition,
+    state: &mut GeneratorState,
+) -> Result<Vec<Asm>, CompileError> {
+    let end_label = state.new_label();
+
+    Ok(join_asm!(
+        generate_node_operations(node.get_child(0), state)?,
+        Asm::Cmp(Param::Rax, Param::Constant(0)),
+        Asm::Jcc(skip_condition, end_label.clone()),
+        generate_node_operations(node.get_child(1), state)?,
+        Asm::Label(end_label),
+        generate_set_condition(Condition::NotEqual, Param::Constant(0))
+    ))
+}
+
+// Only the value the condition selects is evaluated, like the branches of an if
+fn generate_conditional(
+    node: &ParseNode,
+    state: &mut GeneratorState,
+) -> Result<Vec<Asm>, CompileError> {
+    let false_label = state.new_label();
+    let end_label = state.new_label();
+
+    Ok(join_asm!(
+        generate_jump_if_zero(node.get_child(0), &false_label, state)?,
+        generate_node_operations(node.get_child(1), state)?,
+        Asm::Jmp(end_label.clone()),
+        Asm::Label(false_label),
+        generate_node_operations(node.get_child(2), state)?,
+        Asm::Label(end_label)
+    ))
+}
+
+// A static variable with an initializer is defined value by value, with zeros
+// in the gaps between them. Without one it only reserves its size in .bss.
+fn generate_static_variable(node: &ParseNode, state: &mut GeneratorState) -> Vec<Asm> {
+    let NodeType::StaticDecl(label, ctype, is_global) = &node.node_type else {
+        panic!("{:?} is not a static variable", node)
+    };
+    let mut operations = vec![];
+
+    if *is_global {
+        operations.push(Asm::Global(label.clone()));
+    }
+
+    let Some(initializer) = node.children.first() else {
+        return join_asm!(
+            operations,
+            Asm::Alignb(ctype.alignment()),
+            Asm::Label(label.clone()),
+            Asm::Resb(ctype.size())
+        );
+    };
+    operations.push(Asm::Align(ctype.alignment()));
+    operations.push(Asm::Label(label.clone()));
+
+    let zeros = |count| Asm::Times(count, Box::new(Asm::Define(Size::Byte, "0".to_string())));
+    let mut end = 0;
+    for entry in initializer.children.iter() {
+        let NodeType::Initializer(offset, entry_type) = &entry.node_type else {
+            panic!("{:?} is not a flattened initializer", entry)
+        };
+        if *offset > end {
+            operations.push(zeros(offset - end));
         }
-        NodeType::UnaryOp(t) => match t {
-            UnaryOp::Minus => {
-                join_asm!(generate_operations(node.get_child(0)), Asm::Neg(Param::Rax))
+
+        let value = entry.get_child(0);
+        let value = match &value.node_type {
+            NodeType::Const(value, _) => value.to_string(),
+            NodeType::StaticAddress(offset, _) => {
+                let label = escape_symbol(&get_static_label(value.get_child(0), state));
+                match offset {
+                    0 => label,
+                    x if *x < 0 => format!("{} - {}", label, -x),
+                    x => format!("{} + {}", label, x),
+                }
             }
-            UnaryOp::Function(_) => {
-                // TODO: Implement
-                panic!("Function generation not implemented yet.")
+            _ => panic!("{:?} is not a constant", value),
+        };
+        operations.push(Asm::Define(Size::of(entry_type), value));
+        end = offset + entry_type.size();
+    }
+
+    if ctype.size() > end {
+        operations.push(zeros(ctype.size() - end));
+    }
+
+    operations
+}
+
+// Labels of string literals contain a dot, so they are never a C identifier
+fn get_string_label(index: usize) -> String {
+    format!("string.{}", index)
+}
+
+// A string literal is defined with its terminating null character
+fn generate_string(label: String, bytes: &[u8]) -> Vec<Asm> {
+    let values: Vec<String> = bytes
+        .iter()
+        .chain(std::iter::once(&0))
+        .map(|byte| byte.to_string())
+        .collect();
+
+    vec![
+        Asm::Label(label),
+        Asm::Define(Size::Byte, values.join(", ")),
+    ]
+}
+
+// The label of a static variable or string literal
+fn get_static_label(node: &ParseNode, state: &mut GeneratorState) -> String {
+    match &node.node_type {
+        NodeType::StaticVar(label, _) => label.clone(),
+        NodeType::StringLiteral(bytes) => state.add_string(bytes),
+        _ => panic!("{:?} has no label", node),
+    }
+}
+
+// The symbol pass flattened the initializer of an array or struct to a value for
+// each scalar, which is stored at its offset from the lowest byte of the object
+fn generate_initialization(
+    initializer: &ParseNode,
+    offset: usize,
+    state: &mut GeneratorState,
+) -> Result<Vec<Asm>, CompileError> {
+    let mut operations = vec![];
+
+    for value in initializer.children.iter() {
+        let NodeType::Initializer(value_offset, ctype) = &value.node_type else {
+            panic!("{:?} is not a flattened initializer", value);
+        };
+        let displacement = *value_offset as i64 - offset as i64;
+        operations.append(&mut generate_node_operations(value.get_child(0), state)?);
+        operations.append(&mut generate_store(
+            Param::memory(Size::of(ctype), Param::Rbp, displacement),
+            ctype,
+        ));
+    }
+
+    Ok(operations)
+}
+
+// The symbol pass already verified that the target of an assignment is an lvalue
+// Returns the operations that compute the address of the lvalue, into r10 if it
+// is only known at runtime, and the memory operand that accesses the lvalue
+fn generate_lvalue(
+    node: &ParseNode,
+    state: &mut GeneratorState,
+) -> Result<(Vec<Asm>, Param), CompileError> {
+    match &node.node_type {
+        NodeType::Var(_, offset, ctype) => Ok((vec![], Param::local(*offset, ctype))),
+        NodeType::StaticVar(label, ctype) => Ok((vec![], Param::global(label, ctype))),
+        NodeType::StringLiteral(bytes) => {
+            let label = state.add_string(bytes);
+            Ok((vec![], Param::global(&label, &node.get_type())))
+        }
+        NodeType::UnaryOp(UnaryOp::Dereference)
+            if node.get_child(0).node_type == NodeType::BinaryOp(BinaryOp::Plus) =>
+        {
+            generate_indexed_lvalue(node, state)
+        }
+        NodeType::UnaryOp(UnaryOp::Dereference) => Ok((
+            join_asm!(
+                generate_node_operations(node.get_child(0), state)?,
+                Asm::Mov(Param::R10, Param::Rax)
+            ),
+            Param::memory(Size::of(&node.get_type()), Param::R10, 0),
+        )),
+        NodeType::Member(_, offset, ctype) => {
+            let object = node.get_child(0);
+            let (address, operand) = match object.node_type {
+                NodeType::Var(_, _, _)
+                | NodeType::StaticVar(_, _)
+                | NodeType::Member(_, _, _)
+                | NodeType::UnaryOp(UnaryOp::Dereference) => generate_lvalue(object, state)?,
+                // A struct returned by a call or an assignment is only known by its address
+                _ => (
+                    join_asm!(
+                        generate_node_operations(object, state)?,
+                        Asm::Mov(Param::R10, Param::Rax)
+                    ),
+                    Param::memory(Size::Qword, Param::R10, 0),
+                ),
+            };
+
+            Ok((address, operand.offset(*offset as i64, Size::of(ctype))))
+        }
+        _ => panic!("{:?} is not an lvalue", node),
+    }
+}
+
+// Dereferencing a pointer plus an offset, as subscripts do, addresses the element
+// with the pointer in r10 and the offset in r11. An offset that the symbol pass
+// scaled by 2, 4 or 8 uses the scale of the addressing mode instead.
+fn generate_indexed_lvalue(
+    node: &ParseNode,
+    state: &mut GeneratorState,
+) -> Result<(Vec<Asm>, Param), CompileError> {
+    let addition = node.get_child(0);
+    let offset = addition.get_child(1);
+    let (index, scale) = match (&offset.node_type, offset.children.get(1)) {
+        (
+            NodeType::BinaryOp(BinaryOp::Multiplication),
+            Some(ParseNode {
+                node_type: NodeType::Const(scale @ (2 | 4 | 8), _),
+                ..
+            }),
+        ) => (offset.get_child(0), *scale as usize),
+        _ => (offset, 1),
+    };
+
+    Ok((
+        join_asm!(
+            generate_node_operations(addition.get_child(0), state)?,
+            state.push(Param::Rax),
+            generate_node_operations(index, state)?,
+            Asm::Mov(Param::R11, Param::Rax),
+            state.pop(Param::R10)
+        ),
+        Param::Memory(Box::new(Memory {
+            size: Size::of(&node.get_type()),
+            base: Param::R10,
+            index: Some((Param::R11, scale)),
+            displacement: 0,
+        })),
+    ))
+}
+
+// Assigns the value of the right operand to the lvalue, the result of the
+// assignment is the stored value, which stays in rax. A compound assignment
+// performs its operation in the type of the converted right operand, or in the
+// promoted type of the target for shifts.
+fn generate_assignment(
+    node: &ParseNode,
+    operation: Option<&BinaryOp>,
+    state: &mut GeneratorState,
+) -> Result<Vec<Asm>, CompileError> {
+    let target_type = node.get_child(0).get_type();
+    let value = generate_node_operations(node.get_child(1), state)?;
+
+    // The value is pushed while the address of the target is computed
+    let push = state.push(Param::Rax);
+    let (address, target) = generate_lvalue(node.get_child(0), state)?;
+    let pop = state.pop(Param::Rax);
+
+    Ok(join_asm!(
+        if address.is_empty() {
+            value
+        } else {
+            join_asm!(value, push, address, pop)
+        },
+        match operation {
+            Some(operation) => {
+                let operation_type = match operation {
+                    BinaryOp::ShiftLeft | BinaryOp::ShiftRight => target_type.promote(),
+                    _ => node.get_child(1).get_type(),
+                };
+
+                join_asm!(
+                    Asm::Mov(Param::Rcx, Param::Rax),
+                    generate_load(target.clone(), &target_type),
+                    generate_conversion(&target_type, &operation_type),
+                    generate_binary_operation(operation, &operation_type),
+                    generate_conversion(&operation_type, &target_type)
+                )
             }
+            None => vec![],
         },
-        NodeType::BinaryOp(t) => match t {
-            BinaryOp::Minus => join_asm!(
-                generate_operations(node.get_child(1)),
-                Asm::Push(Param::Rax),
-                generate_operations(node.get_child(0)),
-                Asm::Pop(Param::Rbx),
-                Asm::Sub(Param::Rax, Param::Rbx)
+        generate_store(target, &target_type)
+    ))
+}
+
+// Prefix operators result in the updated value, postfix operators in the
+// value from before the update. Pointers step over a whole pointee.
+fn generate_increment(
+    node: &ParseNode,
+    step: i64,
+    is_prefix: bool,
+    state: &mut GeneratorState,
+) -> Result<Vec<Asm>, CompileError> {
+    let (address, target) = generate_lvalue(node.get_child(0), state)?;
+    let ctype = node.get_child(0).get_type();
+    let step = match ctype.get_pointee() {
+        Some(pointee) => step * pointee.size() as i64,
+        None => step,
+    };
+
+    Ok(join_asm!(
+        address,
+        generate_load(target.clone(), &ctype),
+        Asm::Mov(Param::Rcx, Param::Rax),
+        Asm::Add(Param::Rax, Param::Constant(step)),
+        generate_truncation(&ctype),
+        generate_store(target, &ctype),
+        if is_prefix {
+            vec![]
+        } else {
+            vec![Asm::Mov(Param::Rax, Param::Rcx)]
+        }
+    ))
+}
+
+fn generate_node_operations(
+    node: &ParseNode,
+    state: &mut GeneratorState,
+) -> Result<Vec<Asm>, CompileError> {
+    Ok(match &node.node_type {
+        NodeType::Prog(_) => {
+            let mut externs = vec![];
+            let mut functions = vec![Asm::Section(Section::Text)];
+            let mut data = vec![Asm::Section(Section::Data)];
+            let mut bss = vec![Asm::Section(Section::Bss)];
+
+            if !state.is_linked_with_libc {
+                functions.append(&mut generate_start_stub());
+            }
+
+            for child in node.children.iter() {
+                match &child.node_type {
+                    NodeType::ExternDecl(label)
+                        if label == "write" && !state.is_linked_with_libc =>
+                    {
+                        functions.append(&mut generate_write_stub())
+                    }
+                    NodeType::ExternDecl(label) => externs.push(Asm::Extern(label.clone())),
+                    NodeType::StaticDecl(_, _, _) if child.children.is_empty() => {
+                        bss.append(&mut generate_static_variable(child, state))
+                    }
+                    NodeType::StaticDecl(_, _, _) => {
+                        data.append(&mut generate_static_variable(child, state))
+                    }
+                    _ => functions.append(&mut generate_node_operations(child, state)?),
+                }
+            }
+
+            let mut rodata = vec![Asm::Section(Section::Rodata)];
+            for (index, bytes) in state.strings.iter().enumerate() {
+                rodata.append(&mut generate_string(get_string_label(index), bytes));
+            }
+
+            join_asm!(externs, functions, data, bss, rodata)
+        }
+        NodeType::Fn(name, frame_size, return_type, is_global) => {
+            let mut statements = vec![];
+            state.frame_size = *frame_size;
+            state.return_address = None;
+
+            if is_returned_in_memory(return_type) {
+                let offset = state.new_temporary(&CType::Pointer(Box::new(return_type.clone())));
+                state.return_address = Some(offset);
+                statements.push(Asm::Mov(
+                    Param::memory(Size::Qword, Param::Rbp, -(offset as i64)),
+                    Param::Rdi,
+                ));
+            }
+
+            let parameter_types: Vec<CType> = node
+                .children
+                .iter()
+                .filter_map(|child| match &child.node_type {
+                    NodeType::FnParam(_, _, ctype) => Some(ctype.clone()),
+                    _ => None,
+                })
+                .collect();
+            let (locations, _) = classify_arguments(&parameter_types, return_type);
+            let mut locations = locations.into_iter();
+
+            // Structs on the stack are copied through rcx, so the parameters passed
+            // in registers are stored before it is overwritten
+            let mut stack_parameters = vec![];
+            for child in node.children.iter() {
+                match &child.node_type {
+                    NodeType::FnParam(_, offset, ctype) => {
+                        let location = locations.next().expect("Every parameter has a location");
+                        if matches!(location, ArgumentLocation::Stack(_)) {
+                            stack_parameters
+                                .append(&mut generate_param_store(location, *offset, ctype));
+                        } else {
+                            statements.append(&mut generate_param_store(location, *offset, ctype));
+                        }
+                    }
+                    _ => {
+                        statements.append(&mut stack_parameters);
+                        statements.append(&mut generate_node_operations(child, state)?)
+                    }
+                }
+            }
+            statements.append(&mut stack_parameters);
+
+            // Falling off the end of a function returns 0. The frame is reserved after
+            // the statements are generated, as they add their temporaries to it.
+            let frame_size = state.frame_size.next_multiple_of(STACK_ALIGNMENT);
+            join_asm!(
+                if *is_global {
+                    vec![Asm::Global(name.clone())]
+                } else {
+                    vec![]
+                },
+                Asm::FunctionRef(name.clone()),
+                Asm::Push(Param::Rbp),
+                Asm::Mov(Param::Rbp, Param::Rsp),
+                Asm::Sub(Param::Rsp, Param::Constant(frame_size as i64)),
+                statements,
+                Asm::Mov(Param::Rax, Param::Constant(0)),
+                generate_function_return()
+            )
+        }
+        NodeType::Return => match node.children.first() {
+            Some(value) => join_asm!(
+                generate_node_operations(value, state)?,
+                generate_return_value(&value.get_type(), state),
+                generate_function_return()
             ),
-            BinaryOp::Plus => join_asm!(
-                generate_operations(node.get_child(1)),
-                Asm::Push(Param::Rax),
-                generate_operations(node.get_child(0)),
-                Asm::Pop(Param::Rbx),
-                Asm::Add(Param::Rax, Param::Rbx)
+            None => generate_function_return(),
+        },
+        NodeType::Block | NodeType::Declaration(_) => {
+            let mut statements = vec![];
+
+            for child in node.children.iter() {
+                statements.append(&mut generate_node_operations(child, state)?);
+            }
+
+            statements
+        }
+        NodeType::If => generate_if(node, state)?,
+        NodeType::While => generate_while(node, state)?,
+        NodeType::DoWhile => generate_do_while(node, state)?,
+        NodeType::For => generate_for(node, state)?,
+        NodeType::Switch => generate_switch(node, state)?,
+        NodeType::Case => {
+            let NodeType::Const(value, _) = node.get_child(0).node_type else {
+                panic!("{:?} has no constant value", node)
+            };
+            join_asm!(
+                Asm::Label(state.get_case_label(node, Some(value))?),
+                generate_node_operations(node.get_child(1), state)?
+            )
+        }
+        NodeType::Default => join_asm!(
+            Asm::Label(state.get_case_label(node, None)?),
+            generate_node_operations(node.get_child(0), state)?
+        ),
+        NodeType::Label(label) => join_asm!(
+            Asm::Label(label.clone()),
+            generate_node_operations(node.get_child(0), state)?
+        ),
+        // The whole stack frame of a function is reserved on entry and blocks
+        // only reuse parts of it, so a jump out of a block leaves nothing behind
+        NodeType::Goto(label) => vec![Asm::Jmp(label.clone())],
+        NodeType::Break => vec![Asm::Jmp(state.get_break_label(node)?)],
+        NodeType::Continue => vec![Asm::Jmp(state.get_continue_label(node)?)],
+        NodeType::VarDecl(_, offset, ctype) => match node.children.first() {
+            Some(initializer) if initializer.node_type == NodeType::InitializerList => {
+                generate_initialization(initializer, *offset, state)?
+            }
+            Some(initializer) => join_asm!(
+                generate_node_operations(initializer, state)?,
+                generate_store(Param::local(*offset, ctype), ctype)
             ),
-            BinaryOp::Multiplication => join_asm!(
-                generate_operations(node.get_child(1)),
-                Asm::Push(Param::Rax),
-                generate_operations(node.get_child(0)),
-                Asm::Pop(Param::Rbx),
-                Asm::Mul(Param::Rax, Param::Rbx)
+            None => vec![],
+        },
+        NodeType::Var(_, offset, ctype) => {
+            generate_load(Param::local(*offset, ctype), &node.get_type())
+        }
+        NodeType::StaticVar(label, ctype) => {
+            generate_load(Param::global(label, ctype), &node.get_type())
+        }
+        NodeType::StaticAddress(offset, _) => {
+            let (address, operand) = generate_lvalue(node.get_child(0), state)?;
+            join_asm!(
+                address,
+                Asm::Lea(Param::Rax, operand.offset(*offset, Size::Qword))
+            )
+        }
+        NodeType::Const(x, _) => {
+            vec![Asm::Mov(Param::Rax, Param::Constant(*x))]
+        }
+        NodeType::FnCall(name, return_type, is_variadic) => {
+            generate_function_call(node, name, return_type, *is_variadic, state)?
+        }
+        NodeType::Cast(ctype) => join_asm!(
+            generate_node_operations(node.get_child(0), state)?,
+            generate_conversion(&node.get_child(0).get_type(), ctype)
+        ),
+        NodeType::UnaryOp(UnaryOp::AddressOf) => {
+            let (address, operand) = generate_lvalue(node.get_child(0), state)?;
+            join_asm!(address, Asm::Lea(Param::Rax, operand))
+        }
+        NodeType::UnaryOp(UnaryOp::Dereference) | NodeType::Member(_, _, _) => {
+            let (address, operand) = generate_lvalue(node, state)?;
+            join_asm!(address, generate_load(operand, &node.get_type()))
+        }
+        NodeType::UnaryOp(t) => join_asm!(
+            generate_node_operations(node.get_child(0), state)?,
+            generate_unary_operation(t, &node.get_type())
+        ),
+        NodeType::BinaryOp(t) => match t {
+            BinaryOp::LogicalAnd => generate_logical_operation(node, Condition::Equal, state)?,
+            BinaryOp::LogicalOr => generate_logical_operation(node, Condition::NotEqual, state)?,
+            BinaryOp::Division | BinaryOp::Modulo => generate_division(node, t, state)?,
+            _ => join_asm!(
+                generate_binary_operands(node, state)?,
+                generate_binary_operation(t, &node.get_child(0).get_type())
             ),
         },
+        NodeType::Conditional => generate_conditional(node, state)?,
+        NodeType::Comma => join_asm!(
+            generate_node_operations(node.get_child(0), state)?,
+            generate_node_operations(node.get_child(1), state)?
+        ),
+        NodeType::Assignment => generate_assignment(node, None, state)?,
+        NodeType::CompoundAssignment(t) => generate_assignment(node, Some(t), state)?,
+        NodeType::PreIncrement => generate_increment(node, 1, true, state)?,
+        NodeType::PreDecrement => generate_increment(node, -1, true, state)?,
+        NodeType::PostIncrement => generate_increment(node, 1, false, state)?,
+        NodeType::PostDecrement => generate_increment(node, -1, false, state)?,
         _ => {
             vec![]
         }
-    }
+    })
 }
 
-pub fn generate_assembly(operations: &Vec<Asm>) -> String {
-    let mut strings: Vec<String> = vec![
-        " section     .text".to_string(),
-        "global      _start".to_string(),
-    ];
+pub fn generate_operations(
+    node: &ParseNode,
+    is_linked_with_libc: bool,
+) -> Result<Vec<Asm>, CompileError> {
+    let mut state = GeneratorState {
+        label_count: 0,
+        loops: vec![],
+        switches: vec![],
+        frame_size: 0,
+        return_address: None,
+        strings: vec![],
+        stack_depth: 0,
+        is_linked_with_libc,
+    };
 
-    for op in operations {
-        strings.push(op.as_string());
-    }
+    generate_node_operations(node, &mut state)
+}
+
+pub fn generate_assembly(operations: &[Asm]) -> String {
+    let strings: Vec<String> = operations.iter().map(Asm::as_string).collect();
 
     strings.join("\n")
 }
@@ -1,35 +1,103 @@
 // Heavily inspired/copied from https://github.com/onehr/crust/blob/toy/src/lexer.rs
 
+use std::iter::Peekable;
+use std::rc::Rc;
+use std::str::CharIndices;
+
+use crate::error::CompileError;
+use crate::source::{parse_line_marker, span_between, SourceFile, Span};
+use crate::types::CType;
+
 #[derive(Eq, PartialEq, Clone, Debug)]
-pub enum Token {
+pub enum TokenKind {
     Return,
     Int, // keyword int
+    Char,
+    Short,
+    Long,
+    Signed,
+    Unsigned,
+    Bool, // keyword _Bool
+    Void,
+    If,
+    Else,
+    While,
+    Do,
+    For,
+    Break,
+    Continue,
+    Goto,
+    Switch,
+    Case,
+    Default,
+    Sizeof,
+    Struct,
+    Union,
+    Enum,
+    Static,
+    Extern,
+    Auto,
+    Register,
+    Typedef,
+    Const,
+    Volatile,
     Identifier(String),
-    Integer(i32),
+    Integer(u64, CType), // value, type picked from the suffix and base
+    Character(u8),
+    StringLiteral(Vec<u8>), // bytes, without the terminating null character
     LParenthesis,
     RParenthesis,
+    LBracket,
+    RBracket,
     LCurly,
     RCurly,
     Plus,
     Minus,
     Multiplication,
+    Division,
+    Modulo,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+    LogicalAnd,
+    LogicalOr,
+    LogicalNot,
+    BitwiseNot,
+    BitwiseAnd,
+    BitwiseOr,
+    BitwiseXor,
+    ShiftLeft,
+    ShiftRight,
+    Increment,
+    Decrement,
     Assignment,
+    PlusAssignment,
+    MinusAssignment,
+    MultiplicationAssignment,
+    DivisionAssignment,
+    ModuloAssignment,
+    ShiftLeftAssignment,
+    ShiftRightAssignment,
+    BitwiseAndAssignment,
+    BitwiseOrAssignment,
+    BitwiseXorAssignment,
     SemiColon,
+    Colon,
+    Question,
+    Comma,
+    Dot,
+    Ellipsis,
+    Arrow,
+    EndOfFile,
 }
 
-impl Token {
-    pub fn is_operation(&self) -> bool {
-        match self {
-            Token::Plus | Token::Minus | Token::Multiplication => true,
-            _ => false,
-        }
-    }
-    pub fn get_string_value(&self) -> &String {
-        match self {
-            Token::Identifier(x) => x,
-            _ => panic!("Could not get string value from non identifier token."),
-        }
-    }
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
 }
 
 #[macro_export]
@@ -38,9 +106,13 @@ macro_rules! verify_token {
         let token = $tokens.get_token(*$pos)?;
         match token {
             $pattern => Ok(()),
-            _ => Err(format!(
-                "Verify token failed, {:?} at position {} did not conform to pattern",
-                *token, $pos
+            _ => Err($crate::error::CompileError::Parse(
+                $tokens.get_span(*$pos),
+                format!(
+                    "expected {}, found {:?}",
+                    stringify!($pattern).trim_start_matches("TokenKind::"),
+                    token
+                ),
             )),
         }
     }};
@@ -52,25 +124,228 @@ macro_rules! verify_next_token {
         let token = $tokens.get_next_token($pos)?;
         match token {
             $pattern => Ok(()),
-            _ => Err(format!(
-                "Verify next token failed, {:?} at position {} did not conform to pattern",
-                *token, $pos
+            _ => Err($crate::error::CompileError::Parse(
+                $tokens.get_span(*$pos),
+                format!(
+                    "expected {}, found {:?}",
+                    stringify!($pattern).trim_start_matches("TokenKind::"),
+                    token
+                ),
             )),
         }
     }};
 }
 
-pub fn tokenize(program_text: String) -> Result<Vec<Token>, String> {
+// Returns the unsignedness and the number of longs the suffix denotes
+fn parse_integer_suffix(suffix: &str) -> Option<(bool, usize)> {
+    match suffix {
+        "" => Some((false, 0)),
+        "u" | "U" => Some((true, 0)),
+        "l" | "L" => Some((false, 1)),
+        "ll" | "LL" => Some((false, 2)),
+        "ul" | "uL" | "Ul" | "UL" | "lu" | "lU" | "Lu" | "LU" => Some((true, 1)),
+        "ull" | "uLL" | "Ull" | "ULL" | "llu" | "llU" | "LLu" | "LLU" => Some((true, 2)),
+        _ => None,
+    }
+}
+
+// The first type in which the value fits, see section 6.4.4.1 of the C standard.
+// Unsuffixed octal, hexadecimal and binary constants may also become unsigned.
+fn get_integer_literal_type(value: u64, is_decimal: bool, suffix: (bool, usize)) -> Option<CType> {
+    let candidates: &[CType] = match (suffix, is_decimal) {
+        ((false, 0), true) => &[CType::Int, CType::Long, CType::LongLong],
+        ((false, 0), false) => &[
+            CType::Int,
+            CType::UnsignedInt,
+            CType::Long,
+            CType::UnsignedLong,
+            CType::LongLong,
+            CType::UnsignedLongLong,
+        ],
+        ((true, 0), _) => &[
+            CType::UnsignedInt,
+            CType::UnsignedLong,
+            CType::UnsignedLongLong,
+        ],
+        ((false, 1), true) => &[CType::Long, CType::LongLong],
+        ((false, 1), false) => &[
+            CType::Long,
+            CType::UnsignedLong,
+            CType::LongLong,
+            CType::UnsignedLongLong,
+        ],
+        ((true, 1), _) => &[CType::UnsignedLong, CType::UnsignedLongLong],
+        ((false, _), true) => &[CType::LongLong],
+        ((false, _), false) => &[CType::LongLong, CType::UnsignedLongLong],
+        ((true, _), _) => &[CType::UnsignedLongLong],
+    };
+
+    candidates
+        .iter()
+        .find(|candidate| value <= candidate.max_value())
+        .cloned()
+}
+
+pub fn lex_integer(s: &str) -> Result<TokenKind, String> {
+    let (radix, base_name, body) = if let Some(body) = s.strip_prefix("0x").or(s.strip_prefix("0X"))
+    {
+        (16, "hexadecimal", body)
+    } else if let Some(body) = s.strip_prefix("0b").or(s.strip_prefix("0B")) {
+        (2, "binary", body)
+    } else if s.len() > 1 && s.starts_with('0') {
+        (8, "octal", &s[1..])
+    } else {
+        (10, "decimal", s)
+    };
+
+    let digits_end = body
+        .find(|c: char| !c.is_digit(radix.max(10)))
+        .unwrap_or(body.len());
+    let (digits, suffix) = body.split_at(digits_end);
+
+    if digits.is_empty() && radix != 8 {
+        return Err(format!("no digits in {} constant '{}'", base_name, s));
+    }
+    if let Some(digit) = digits.chars().find(|c| !c.is_digit(radix)) {
+        return Err(format!(
+            "invalid digit '{}' in {} constant",
+            digit, base_name
+        ));
+    }
+    let suffix = parse_integer_suffix(suffix)
+        .ok_or_else(|| format!("invalid suffix '{}' on integer constant", suffix))?;
+
+    let value = match digits {
+        "" => 0,
+        _ => u64::from_str_radix(digits, radix)
+            .map_err(|_| format!("integer literal '{}' is too large", s))?,
+    };
+
+    match get_integer_literal_type(value, radix == 10, suffix) {
+        Some(ctype) => Ok(TokenKind::Integer(value, ctype)),
+        None => Err(format!("integer literal '{}' is too large for its type", s)),
+    }
+}
+
+// Lexes the escape sequence following a backslash into its byte value
+fn lex_escape_sequence(it: &mut Peekable<CharIndices>, quote: char) -> Result<u8, String> {
+    match it.next() {
+        Some((_, 'n')) => Ok(b'\n'),
+        Some((_, 't')) => Ok(b'\t'),
+        Some((_, 'r')) => Ok(b'\r'),
+        Some((_, 'a')) => Ok(0x07),
+        Some((_, 'b')) => Ok(0x08),
+        Some((_, 'f')) => Ok(0x0c),
+        Some((_, 'v')) => Ok(0x0b),
+        Some((_, 'e')) => Ok(0x1b), // GNU extension
+        Some((_, '\\')) => Ok(b'\\'),
+        Some((_, '\'')) => Ok(b'\''),
+        Some((_, '"')) => Ok(b'"'),
+        Some((_, '?')) => Ok(b'?'),
+        Some((_, first @ '0'..='7')) => {
+            let mut value = first.to_digit(8).unwrap();
+            for _ in 0..2 {
+                match it.peek().and_then(|&(_, c)| c.to_digit(8)) {
+                    Some(digit) => {
+                        value = value * 8 + digit;
+                        it.next();
+                    }
+                    None => break,
+                }
+            }
+            u8::try_from(value).map_err(|_| "octal escape sequence out of range".to_string())
+        }
+        Some((_, 'x')) => {
+            let mut value: u32 = 0;
+            let mut n_digits = 0;
+            while let Some(digit) = it.peek().and_then(|&(_, c)| c.to_digit(16)) {
+                value = value.saturating_mul(16).saturating_add(digit);
+                n_digits += 1;
+                it.next();
+            }
+            if n_digits == 0 {
+                return Err("\\x used with no following hex digits".to_string());
+            }
+            u8::try_from(value).map_err(|_| "hex escape sequence out of range".to_string())
+        }
+        Some((_, c)) => Err(format!("unknown escape sequence '\\{}'", c)),
+        None => Err(format!("missing terminating {} character", quote)),
+    }
+}
+
+// Lexes the rest of a character constant after the opening quote
+pub fn lex_character_constant(it: &mut Peekable<CharIndices>) -> Result<u8, String> {
+    let value = match it.next() {
+        Some((_, '\\')) => lex_escape_sequence(it, '\'')?,
+        Some((_, '\'')) => return Err("empty character constant".to_string()),
+        Some((_, '\n')) | None => return Err("missing terminating ' character".to_string()),
+        Some((_, c)) if c.is_ascii() => c as u8,
+        Some((_, c)) => {
+            return Err(format!(
+                "multi-byte character '{}' in character constant",
+                c
+            ))
+        }
+    };
+
+    match it.next() {
+        Some((_, '\'')) => Ok(value),
+        Some((_, '\n')) | None => Err("missing terminating ' character".to_string()),
+        Some(_) => Err("multi-character character constant".to_string()),
+    }
+}
+
+// Lexes the rest of a string literal after the opening quote. Characters outside
+// of ASCII are stored as their UTF-8 bytes.
+fn lex_string_literal(it: &mut Peekable<CharIndices>) -> Result<Vec<u8>, String> {
+    let mut bytes = vec![];
+
+    loop {
+        match it.next() {
+            Some((_, '"')) => return Ok(bytes),
+            Some((_, '\\')) => bytes.push(lex_escape_sequence(it, '"')?),
+            Some((_, '\n')) | None => return Err("missing terminating \" character".to_string()),
+            Some((_, c)) => {
+                let mut buffer = [0; 4];
+                bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+            }
+        }
+    }
+}
+
+// Skips up to, but not including, the newline that ends the comment
+fn skip_line_comment(it: &mut Peekable<CharIndices>) {
+    while let Some(&(_, c)) = it.peek() {
+        if c == '\n' {
+            break;
+        }
+        it.next();
+    }
+}
+
+// Skips up to and including the closing */, returns false if the comment is never closed
+fn skip_block_comment(it: &mut Peekable<CharIndices>) -> bool {
+    while let Some((_, c)) = it.next() {
+        if c == '*' && matches!(it.peek(), Some((_, '/'))) {
+            it.next();
+            return true;
+        }
+    }
+
+    false
+}
+
+pub fn tokenize(file: &Rc<SourceFile>) -> Result<Vec<Token>, CompileError> {
     let mut tokens = vec![];
-    let mut it = program_text.chars().peekable();
+    let mut it = file.content.char_indices().peekable();
 
-    while let Some(&c) = it.peek() {
-        match c {
+    while let Some(&(start, c)) = it.peek() {
+        let kind = match c {
             'a'..='z' | 'A'..='Z' | '_' => {
                 it.next();
                 let mut s = String::new();
                 s.push(c);
-                while let Some(&tmp) = it.peek() {
+                while let Some(&(_, tmp)) = it.peek() {
                     match tmp {
                         'a'..='z' | 'A'..='Z' | '0'..='9' | '_' => {
                             s.push(tmp);
@@ -82,9 +357,38 @@ pub fn tokenize(program_text: String) -> Result<Vec<Token>, String> {
                     }
                 }
                 match s.as_ref() {
-                    "return" => tokens.push(Token::Return),
-                    "int" => tokens.push(Token::Int),
-                    _ => tokens.push(Token::Identifier(s)),
+                    "return" => TokenKind::Return,
+                    "int" => TokenKind::Int,
+                    "char" => TokenKind::Char,
+                    "short" => TokenKind::Short,
+                    "long" => TokenKind::Long,
+                    "signed" => TokenKind::Signed,
+                    "unsigned" => TokenKind::Unsigned,
+                    "_Bool" => TokenKind::Bool,
+                    "void" => TokenKind::Void,
+                    "if" => TokenKind::If,
+                    "else" => TokenKind::Else,
+                    "while" => TokenKind::While,
+                    "do" => TokenKind::Do,
+                    "for" => TokenKind::For,
+                    "break" => TokenKind::Break,
+                    "continue" => TokenKind::Continue,
+                    "goto" => TokenKind::Goto,
+                    "switch" => TokenKind::Switch,
+                    "case" => TokenKind::Case,
+                    "default" => TokenKind::Default,
+                    "sizeof" => TokenKind::Sizeof,
+                    "struct" => TokenKind::Struct,
+                    "union" => TokenKind::Union,
+                    "enum" => TokenKind::Enum,
+                    "static" => TokenKind::Static,
+                    "extern" => TokenKind::Extern,
+                    "auto" => TokenKind::Auto,
+                    "register" => TokenKind::Register,
+                    "typedef" => TokenKind::Typedef,
+                    "const" => TokenKind::Const,
+                    "volatile" => TokenKind::Volatile,
+                    _ => TokenKind::Identifier(s),
                 }
             }
 
@@ -92,10 +396,10 @@ pub fn tokenize(program_text: String) -> Result<Vec<Token>, String> {
                 it.next();
                 let mut s = String::new();
                 s.push(c);
-                while let Some(&tmp) = it.peek() {
+                while let Some(&(_, tmp)) = it.peek() {
                     match tmp {
-                        '0'..='9' => {
-                            // Only works on integers for now
+                        // Digits of every base and the suffixes, validated afterwards
+                        'a'..='z' | 'A'..='Z' | '0'..='9' | '_' => {
                             s.push(tmp);
                             it.next();
                         }
@@ -104,55 +408,288 @@ pub fn tokenize(program_text: String) -> Result<Vec<Token>, String> {
                         }
                     }
                 }
-                tokens.push(Token::Integer(s.parse::<i32>().unwrap()));
+                lex_integer(&s).map_err(|message| {
+                    CompileError::Lex(span_between(file, start, start + s.len()), message)
+                })?
+            }
+            '\'' => {
+                it.next();
+                let value = lex_character_constant(&mut it).map_err(|message| {
+                    let end = it.peek().map_or(file.content.len(), |&(i, _)| i);
+                    CompileError::Lex(span_between(file, start, end), message)
+                })?;
+                TokenKind::Character(value)
+            }
+            '"' => {
+                it.next();
+                let bytes = lex_string_literal(&mut it).map_err(|message| {
+                    let end = it.peek().map_or(file.content.len(), |&(i, _)| i);
+                    CompileError::Lex(span_between(file, start, end), message)
+                })?;
+                TokenKind::StringLiteral(bytes)
             }
             '+' => {
                 it.next();
-                tokens.push(Token::Plus);
+                match it.peek() {
+                    Some((_, '+')) => {
+                        it.next();
+                        TokenKind::Increment
+                    }
+                    Some((_, '=')) => {
+                        it.next();
+                        TokenKind::PlusAssignment
+                    }
+                    _ => TokenKind::Plus,
+                }
             }
             '-' => {
                 it.next();
-                tokens.push(Token::Minus);
+                match it.peek() {
+                    Some((_, '-')) => {
+                        it.next();
+                        TokenKind::Decrement
+                    }
+                    Some((_, '=')) => {
+                        it.next();
+                        TokenKind::MinusAssignment
+                    }
+                    Some((_, '>')) => {
+                        it.next();
+                        TokenKind::Arrow
+                    }
+                    _ => TokenKind::Minus,
+                }
             }
             '*' => {
                 it.next();
-                tokens.push(Token::Multiplication);
+                match it.peek() {
+                    Some((_, '=')) => {
+                        it.next();
+                        TokenKind::MultiplicationAssignment
+                    }
+                    _ => TokenKind::Multiplication,
+                }
+            }
+            '/' => {
+                it.next();
+                match it.peek() {
+                    Some((_, '/')) => {
+                        skip_line_comment(&mut it);
+                        continue;
+                    }
+                    Some((_, '*')) => {
+                        it.next();
+                        if !skip_block_comment(&mut it) {
+                            return Err(CompileError::Lex(
+                                span_between(file, start, start + 2),
+                                "unterminated comment".to_string(),
+                            ));
+                        }
+                        continue;
+                    }
+                    Some((_, '=')) => {
+                        it.next();
+                        TokenKind::DivisionAssignment
+                    }
+                    _ => TokenKind::Division,
+                }
+            }
+            '%' => {
+                it.next();
+                match it.peek() {
+                    Some((_, '=')) => {
+                        it.next();
+                        TokenKind::ModuloAssignment
+                    }
+                    _ => TokenKind::Modulo,
+                }
+            }
+            '<' => {
+                it.next();
+                match it.peek() {
+                    Some((_, '=')) => {
+                        it.next();
+                        TokenKind::LessOrEqual
+                    }
+                    Some((_, '<')) => {
+                        it.next();
+                        match it.peek() {
+                            Some((_, '=')) => {
+                                it.next();
+                                TokenKind::ShiftLeftAssignment
+                            }
+                            _ => TokenKind::ShiftLeft,
+                        }
+                    }
+                    _ => TokenKind::Less,
+                }
+            }
+            '>' => {
+                it.next();
+                match it.peek() {
+                    Some((_, '=')) => {
+                        it.next();
+                        TokenKind::GreaterOrEqual
+                    }
+                    Some((_, '>')) => {
+                        it.next();
+                        match it.peek() {
+                            Some((_, '=')) => {
+                                it.next();
+                                TokenKind::ShiftRightAssignment
+                            }
+                            _ => TokenKind::ShiftRight,
+                        }
+                    }
+                    _ => TokenKind::Greater,
+                }
             }
             '=' => {
                 it.next();
-                tokens.push(Token::Assignment);
+                match it.peek() {
+                    Some((_, '=')) => {
+                        it.next();
+                        TokenKind::Equal
+                    }
+                    _ => TokenKind::Assignment,
+                }
+            }
+            '!' => {
+                it.next();
+                match it.peek() {
+                    Some((_, '=')) => {
+                        it.next();
+                        TokenKind::NotEqual
+                    }
+                    _ => TokenKind::LogicalNot,
+                }
+            }
+            '&' => {
+                it.next();
+                match it.peek() {
+                    Some((_, '&')) => {
+                        it.next();
+                        TokenKind::LogicalAnd
+                    }
+                    Some((_, '=')) => {
+                        it.next();
+                        TokenKind::BitwiseAndAssignment
+                    }
+                    _ => TokenKind::BitwiseAnd,
+                }
+            }
+            '|' => {
+                it.next();
+                match it.peek() {
+                    Some((_, '|')) => {
+                        it.next();
+                        TokenKind::LogicalOr
+                    }
+                    Some((_, '=')) => {
+                        it.next();
+                        TokenKind::BitwiseOrAssignment
+                    }
+                    _ => TokenKind::BitwiseOr,
+                }
+            }
+            '^' => {
+                it.next();
+                match it.peek() {
+                    Some((_, '=')) => {
+                        it.next();
+                        TokenKind::BitwiseXorAssignment
+                    }
+                    _ => TokenKind::BitwiseXor,
+                }
+            }
+            '~' => {
+                it.next();
+                TokenKind::BitwiseNot
             }
             '(' => {
                 it.next();
-                tokens.push(Token::LParenthesis);
+                TokenKind::LParenthesis
+            }
+            '[' => {
+                it.next();
+                TokenKind::LBracket
+            }
+            ']' => {
+                it.next();
+                TokenKind::RBracket
             }
             ')' => {
                 it.next();
-                tokens.push(Token::RParenthesis);
+                TokenKind::RParenthesis
             }
             '{' => {
                 it.next();
-                tokens.push(Token::LCurly);
+                TokenKind::LCurly
             }
             '}' => {
                 it.next();
-                tokens.push(Token::RCurly);
+                TokenKind::RCurly
             }
             ';' => {
                 it.next();
-                tokens.push(Token::SemiColon);
+                TokenKind::SemiColon
             }
-            _ => {
+            ':' => {
                 it.next();
+                TokenKind::Colon
             }
-        }
+            '?' => {
+                it.next();
+                TokenKind::Question
+            }
+            ',' => {
+                it.next();
+                TokenKind::Comma
+            }
+            '.' if file.content[start..].starts_with("...") => {
+                it.nth(2);
+                TokenKind::Ellipsis
+            }
+            '.' => {
+                it.next();
+                TokenKind::Dot
+            }
+            // Line markers left by the preprocessor
+            '#' if (start == 0 || file.content[..start].ends_with('\n'))
+                && parse_line_marker(file.content[start..].lines().next().unwrap()).is_some() =>
+            {
+                skip_line_comment(&mut it);
+                continue;
+            }
+            ' ' | '\t' | '\n' | '\r' | '\x0b' | '\x0c' => {
+                it.next();
+                continue;
+            }
+            _ => {
+                return Err(CompileError::Lex(
+                    span_between(file, start, start + c.len_utf8()),
+                    format!("invalid character '{}' in program", c),
+                ))
+            }
+        };
+
+        let end = it.peek().map_or(file.content.len(), |&(i, _)| i);
+        tokens.push(Token {
+            kind,
+            span: span_between(file, start, end),
+        });
     }
 
+    tokens.push(Token {
+        kind: TokenKind::EndOfFile,
+        span: span_between(file, file.content.len(), file.content.len()),
+    });
+
     Ok(tokens)
 }
 
 pub fn print_tokens(tokens: &[Token]) {
     for (i, token) in tokens.iter().enumerate() {
-        println!("{}\t| {:?}", i, token);
+        println!("{}\t| {:?}\t{:?}", i, token.span, token.kind);
     }
 }
@@ -1,5 +1,9 @@
 pub mod constants;
+pub mod preprocessor;
 pub mod lexer;
 pub mod parser;
 pub mod symbolpass;
 pub mod generator;
+pub mod source;
+pub mod error;
+pub mod types;
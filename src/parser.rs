@@ -1,10 +1,19 @@
-use crate::lexer::Token;
-use crate::{verify_token, verify_next_token};
+use std::collections::HashMap;
+
+use crate::error::CompileError;
+use crate::lexer::{Token, TokenKind};
+use crate::source::Span;
+use crate::types::{CType, StructKind, StructType};
+use crate::verify_next_token;
 
 #[derive(Eq, PartialEq, Clone, Debug)]
 pub enum UnaryOp {
+    Plus,
     Minus,
-    Function(String),
+    LogicalNot,
+    BitwiseNot,
+    AddressOf,
+    Dereference,
 }
 
 #[derive(Eq, PartialEq, Clone, Debug)]
@@ -12,31 +21,99 @@ pub enum BinaryOp {
     Plus,
     Minus,
     Multiplication,
+    Division,
+    Modulo,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+    LogicalAnd,
+    LogicalOr,
+    BitwiseAnd,
+    BitwiseOr,
+    BitwiseXor,
+    ShiftLeft,
+    ShiftRight,
+}
+
+// Without a storage class, a variable in a function is automatic and a variable
+// at file scope is static with external linkage
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum StorageClass {
+    Auto,
+    Static,
+    Extern,
+    Typedef, // declares names for types, rather than objects
 }
 
 #[derive(Eq, PartialEq, Clone, Debug)]
 pub enum NodeType {
     Prog(String),
-    Fn(String, usize),      // name, number of variables
-    VarDecl(String, usize), // name, offset
-    Var(String, usize),     // name, offset
+    Fn(String, usize, CType, bool), // name, stack frame size, return type, is global
+    FnParam(String, usize, CType),  // name, offset, type
+    // name, return type, is variadic, is global, children are the parameters
+    FnDecl(String, CType, bool, bool),
+    VarDecl(String, usize, CType), // name, offset, type, child is the optional initializer
+    Var(String, usize, CType),     // name, offset, type
+    Member(String, usize, CType),  // name, offset in the struct, type, child is the struct
+    StructDecl(CType),             // children are the nested struct definitions and the members
+    MemberDecl(String, CType),     // name, type
+    EnumDecl(String),              // tag, children are the enumerators
+    Enumerator(String),            // name, child is the optional value
+    Initializer(usize, CType),     // offset in the object, type, child is the value of the scalar
     Return,
-    Const(i32),
+    StaticDecl(String, CType, bool), // label, type, is global, child is the optional initializer
+    StaticVar(String, CType),        // label, type, a variable with static storage duration
+    ExternDecl(String),              // label of a variable or function defined in another file
+    Block,
+    Declaration(StorageClass), // children are the struct definitions and the declared variables
+    InitializerList,           // children are the initializers of the elements
+    SizeOf,                    // operand, which is not evaluated
+    TypeName(CType),           // the operand of sizeof when it is a type
+    If,                        // condition, then, optional else
+    While,                     // condition, body
+    DoWhile,                   // body, condition
+    For,                       // init, condition, post, body
+    Switch,                    // controlling expression, body
+    Case,                      // value, statement
+    Default,                   // statement
+    Label(String),             // name, child is the labeled statement
+    Goto(String),              // name of the label
+    Break,
+    Continue,
+    Empty,
+    Const(i64, CType),           // value, type
+    StaticAddress(i64, CType),   // offset, type, the address of the static object in its child
+    StringLiteral(Vec<u8>),      // bytes, without the terminating null character
+    FnCall(String, CType, bool), // name, return type, is variadic, children are the arguments
+    Cast(CType),
     UnaryOp(UnaryOp),
     BinaryOp(BinaryOp),
+    Assignment,                   // target, value
+    CompoundAssignment(BinaryOp), // target, value
+    Conditional,                  // condition, value if true, value if false
+    Comma,                        // operand that is evaluated first, the value
+    PreIncrement,
+    PreDecrement,
+    PostIncrement,
+    PostDecrement,
 }
 
 #[derive(Eq, PartialEq, Clone, Debug)]
 pub struct ParseNode {
     pub node_type: NodeType,
     pub children: Vec<ParseNode>,
+    pub span: Span,
 }
 
 impl ParseNode {
-    pub fn new(node_type: NodeType) -> ParseNode {
+    pub fn new(node_type: NodeType, span: Span) -> ParseNode {
         ParseNode {
             node_type,
             children: Vec::new(),
+            span,
         }
     }
 
@@ -54,6 +131,64 @@ impl ParseNode {
     pub fn is_operation(&self) -> bool {
         matches!(self.node_type, NodeType::UnaryOp(_) | NodeType::BinaryOp(_))
     }
+
+    // The type of an expression, the symbol pass fills in the types of variables and
+    // calls, and converts the operands of an operation to the type it is performed in.
+    // The value read from an object is unqualified.
+    pub fn get_type(&self) -> CType {
+        self.get_qualified_type().unqualified().clone()
+    }
+
+    // The type of an expression with its qualifiers, which tell whether the object
+    // it designates may be modified. A member of a const struct is const too.
+    pub fn get_qualified_type(&self) -> CType {
+        match &self.node_type {
+            NodeType::Member(_, _, ctype) if self.get_child(0).get_qualified_type().is_const() => {
+                ctype.to_const()
+            }
+            NodeType::Var(_, _, ctype)
+            | NodeType::StaticVar(_, ctype)
+            | NodeType::StaticAddress(_, ctype)
+            | NodeType::Member(_, _, ctype)
+            | NodeType::Const(_, ctype)
+            | NodeType::FnCall(_, ctype, _)
+            | NodeType::Cast(ctype)
+            | NodeType::TypeName(ctype) => ctype.clone(),
+            NodeType::StringLiteral(bytes) => CType::Array(Box::new(CType::Char), bytes.len() + 1),
+            NodeType::UnaryOp(UnaryOp::LogicalNot) => CType::Int,
+            NodeType::UnaryOp(UnaryOp::AddressOf) => {
+                CType::Pointer(Box::new(self.get_child(0).get_qualified_type()))
+            }
+            NodeType::UnaryOp(UnaryOp::Dereference) => self
+                .get_child(0)
+                .get_type()
+                .get_pointee()
+                .unwrap_or_else(|| panic!("{:?} dereferences a non-pointer", self))
+                .clone(),
+            NodeType::UnaryOp(_) => self.get_child(0).get_type(),
+            NodeType::BinaryOp(operation) => match operation {
+                BinaryOp::Less
+                | BinaryOp::LessOrEqual
+                | BinaryOp::Greater
+                | BinaryOp::GreaterOrEqual
+                | BinaryOp::Equal
+                | BinaryOp::NotEqual
+                | BinaryOp::LogicalAnd
+                | BinaryOp::LogicalOr => CType::Int,
+                _ => self.get_child(0).get_type(),
+            },
+            NodeType::Assignment
+            | NodeType::CompoundAssignment(_)
+            | NodeType::PreIncrement
+            | NodeType::PreDecrement
+            | NodeType::PostIncrement
+            | NodeType::PostDecrement => self.get_child(0).get_type(),
+            // The symbol pass converts both values of a conditional to the same type
+            NodeType::Conditional => self.get_child(1).get_type(),
+            NodeType::Comma => self.get_child(1).get_type(),
+            _ => panic!("{:?} is not an expression", self),
+        }
+    }
 }
 
 fn print_parse_node_tree(node: &ParseNode, indent: usize) {
@@ -68,234 +203,1704 @@ pub fn print_ast(node: &ParseNode) {
 }
 
 trait GetTokenOrPrintErr {
-    fn get_token(&self, pos: usize) -> Result<&Token, String>;
-    fn get_next_token(&self, pos: &mut usize) -> Result<&Token, String>;
+    fn get_token(&self, pos: usize) -> Result<&TokenKind, CompileError>;
+    fn get_next_token(&self, pos: &mut usize) -> Result<&TokenKind, CompileError>;
+    fn get_span(&self, pos: usize) -> Span;
 }
 
 impl GetTokenOrPrintErr for &[Token] {
-    fn get_token(&self, pos: usize) -> Result<&Token, String> {
+    fn get_token(&self, pos: usize) -> Result<&TokenKind, CompileError> {
         match self.get(pos) {
-            Some(x) => Ok(x),
-            None => Err(format!(
-                "No token exists at {}, the last token is {:?} at {}. ",
-                pos,
-                self.last(),
-                self.len() - 1
+            Some(x) => Ok(&x.kind),
+            None => Err(CompileError::Parse(
+                self.get_span(pos),
+                "unexpected end of file".to_string(),
             )),
         }
     }
 
-    fn get_next_token(&self, pos: &mut usize) -> Result<&Token, String> {
+    fn get_next_token(&self, pos: &mut usize) -> Result<&TokenKind, CompileError> {
         *pos += 1;
         let token = &self.get_token(*pos)?;
         Ok(token)
     }
+
+    // The lexer always ends the tokens with an end of file token
+    fn get_span(&self, pos: usize) -> Span {
+        match self.get(pos) {
+            Some(x) => x.span.clone(),
+            None => self
+                .last()
+                .expect("Tokens should end with EndOfFile")
+                .span
+                .clone(),
+        }
+    }
+}
+
+fn parse_identifier(tokens: &[Token], pos: &mut usize) -> Result<String, CompileError> {
+    match tokens.get_next_token(pos)? {
+        TokenKind::Identifier(name) => Ok(name.clone()),
+        token => Err(CompileError::Parse(
+            tokens.get_span(*pos),
+            format!("expected identifier, found {:?}", token),
+        )),
+    }
+}
+
+// The ordinary identifiers declared in each scope, with the type of those declared
+// as a typedef. A typedef name starts a declaration where any other identifier
+// starts an expression, so the parser keeps track of which is which. A variable,
+// function or enumeration constant in an inner scope hides a typedef of the same name.
+struct Typedefs {
+    scopes: Vec<HashMap<String, Option<CType>>>,
+}
+
+impl Typedefs {
+    fn new() -> Typedefs {
+        Typedefs {
+            scopes: vec![HashMap::new()],
+        }
+    }
+
+    fn enter_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn leave_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn get_typedef(&self, name: &str) -> Option<&CType> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .and_then(|ctype| ctype.as_ref())
+    }
+
+    // Declares a typedef name with its type, or another ordinary identifier without
+    // one. A typedef may be repeated in the same scope, as long as the type is the same.
+    fn declare(
+        &mut self,
+        name: &str,
+        ctype: Option<CType>,
+        span: &Span,
+    ) -> Result<(), CompileError> {
+        let scope = self
+            .scopes
+            .last_mut()
+            .expect("There is always a file scope");
+
+        match (scope.get(name), &ctype) {
+            (Some(Some(previous)), Some(ctype)) if previous != ctype => Err(CompileError::Parse(
+                span.clone(),
+                format!("conflicting types for '{}'", name),
+            )),
+            (Some(previous), _) if previous.is_some() != ctype.is_some() => {
+                Err(CompileError::Parse(
+                    span.clone(),
+                    format!("'{}' redeclared as different kind of symbol", name),
+                ))
+            }
+            _ => {
+                scope.insert(name.to_string(), ctype);
+                Ok(())
+            }
+        }
+    }
+}
+
+fn is_type_specifier(token: &TokenKind) -> bool {
+    matches!(
+        token,
+        TokenKind::Int
+            | TokenKind::Char
+            | TokenKind::Short
+            | TokenKind::Long
+            | TokenKind::Signed
+            | TokenKind::Unsigned
+            | TokenKind::Bool
+            | TokenKind::Void
+            | TokenKind::Struct
+            | TokenKind::Union
+            | TokenKind::Enum
+    )
+}
+
+fn is_type_qualifier(token: &TokenKind) -> bool {
+    matches!(token, TokenKind::Const | TokenKind::Volatile)
+}
+
+fn is_storage_class(token: &TokenKind) -> bool {
+    matches!(
+        token,
+        TokenKind::Static
+            | TokenKind::Extern
+            | TokenKind::Auto
+            | TokenKind::Register
+            | TokenKind::Typedef
+    )
+}
+
+// A type name, as in a cast or sizeof, starts with a type specifier, a qualifier or
+// a typedef name
+fn is_type_name_start(token: &TokenKind, typedefs: &Typedefs) -> bool {
+    match token {
+        TokenKind::Identifier(name) => typedefs.get_typedef(name).is_some(),
+        token => is_type_specifier(token) || is_type_qualifier(token),
+    }
+}
+
+fn is_declaration_start(token: &TokenKind, typedefs: &Typedefs) -> bool {
+    is_storage_class(token) || is_type_name_start(token, typedefs)
+}
+
+// Whether the token continues the specifiers, an identifier is only a typedef name
+// when no type specifier came before it, so in `T T;` the second T is declared
+fn is_specifier(
+    token: &TokenKind,
+    has_type: bool,
+    allows_storage_class: bool,
+    typedefs: &Typedefs,
+) -> bool {
+    match token {
+        TokenKind::Identifier(_) => !has_type && is_type_name_start(token, typedefs),
+        token => {
+            is_type_specifier(token)
+                || is_type_qualifier(token)
+                || (allows_storage_class && is_storage_class(token))
+        }
+    }
+}
+
+// The specifiers of a declaration may appear in any order, such as in
+// `long static unsigned int`, so the type specifiers are counted first and then
+// combined into a type. The first specifier is the token at pos. The struct and
+// enum definitions among them are added to the definitions. Of the qualifiers only
+// const is kept on the type, volatile makes no difference to the generated code.
+fn parse_specifiers(
+    tokens: &[Token],
+    pos: &mut usize,
+    definitions: &mut Vec<ParseNode>,
+    allows_storage_class: bool,
+    typedefs: &mut Typedefs,
+) -> Result<(StorageClass, CType), CompileError> {
+    let span = tokens.get_span(*pos);
+    let mut storage_class = None;
+    // The type given by void, a struct, union or enum specifier, or a typedef name
+    let mut named_type = None;
+    let (mut chars, mut shorts, mut ints, mut longs, mut bools) = (0, 0, 0, 0, 0);
+    let (mut signed, mut unsigned) = (0, 0);
+    let mut is_const = false;
+
+    loop {
+        match tokens.get_token(*pos)? {
+            token if allows_storage_class && is_storage_class(token) => {
+                if storage_class.is_some() {
+                    return Err(CompileError::Parse(
+                        tokens.get_span(*pos),
+                        "multiple storage classes in declaration specifiers".to_string(),
+                    ));
+                }
+                storage_class = Some(match token {
+                    TokenKind::Static => StorageClass::Static,
+                    TokenKind::Extern => StorageClass::Extern,
+                    TokenKind::Typedef => StorageClass::Typedef,
+                    _ => StorageClass::Auto,
+                });
+            }
+            TokenKind::Const => is_const = true,
+            TokenKind::Volatile => (),
+            TokenKind::Char => chars += 1,
+            TokenKind::Short => shorts += 1,
+            TokenKind::Int => ints += 1,
+            TokenKind::Long => longs += 1,
+            TokenKind::Bool => bools += 1,
+            TokenKind::Signed => signed += 1,
+            TokenKind::Unsigned => unsigned += 1,
+            TokenKind::Void | TokenKind::Struct | TokenKind::Union | TokenKind::Enum
+                if named_type.is_some() =>
+            {
+                return Err(CompileError::Parse(
+                    span,
+                    "invalid combination of type specifiers".to_string(),
+                ))
+            }
+            TokenKind::Void => named_type = Some(CType::Void),
+            TokenKind::Struct | TokenKind::Union => {
+                named_type = Some(parse_struct_specifier(tokens, pos, definitions, typedefs)?);
+            }
+            TokenKind::Enum => {
+                named_type = Some(parse_enum_specifier(tokens, pos, definitions, typedefs)?);
+            }
+            TokenKind::Identifier(name) if typedefs.get_typedef(name).is_some() => {
+                named_type = typedefs.get_typedef(name).cloned();
+            }
+            token => {
+                return Err(CompileError::Parse(
+                    span,
+                    format!("expected a type, found {:?}", token),
+                ))
+            }
+        }
+
+        let specifiers = chars + shorts + ints + longs + bools + signed + unsigned;
+        let has_type = named_type.is_some() || specifiers > 0;
+        if !is_specifier(
+            tokens.get_token(*pos + 1)?,
+            has_type,
+            allows_storage_class,
+            typedefs,
+        ) {
+            break;
+        }
+        *pos += 1;
+    }
+    let storage_class = storage_class.unwrap_or(StorageClass::Auto);
+
+    let is_unsigned = unsigned > 0;
+    let ctype = match (signed + unsigned, chars, shorts, ints, longs, bools) {
+        (0, 0, 0, 0, 0, 0) => match named_type {
+            Some(ctype) => ctype,
+            None => {
+                return Err(CompileError::Parse(
+                    span,
+                    "missing type specifier in declaration".to_string(),
+                ))
+            }
+        },
+        _ if named_type.is_some() => {
+            return Err(CompileError::Parse(
+                span,
+                "invalid combination of type specifiers".to_string(),
+            ))
+        }
+        (0, 0, 0, 0, 0, 1) => CType::Bool,
+        (0 | 1, 1, 0, 0, 0, 0) if is_unsigned => CType::UnsignedChar,
+        (0 | 1, 1, 0, 0, 0, 0) => CType::Char,
+        (0 | 1, 0, 1, 0 | 1, 0, 0) if is_unsigned => CType::UnsignedShort,
+        (0 | 1, 0, 1, 0 | 1, 0, 0) => CType::Short,
+        (0 | 1, 0, 0, 0 | 1, 0, 0) if is_unsigned => CType::UnsignedInt,
+        (0 | 1, 0, 0, 0 | 1, 0, 0) => CType::Int,
+        (0 | 1, 0, 0, 0 | 1, 1, 0) if is_unsigned => CType::UnsignedLong,
+        (0 | 1, 0, 0, 0 | 1, 1, 0) => CType::Long,
+        (0 | 1, 0, 0, 0 | 1, 2, 0) if is_unsigned => CType::UnsignedLongLong,
+        (0 | 1, 0, 0, 0 | 1, 2, 0) => CType::LongLong,
+        _ => {
+            return Err(CompileError::Parse(
+                span,
+                "invalid combination of type specifiers".to_string(),
+            ))
+        }
+    };
+    let ctype = if is_const { ctype.to_const() } else { ctype };
+
+    Ok((storage_class, ctype))
+}
+
+// Without a storage class among the specifiers, the storage class is automatic
+fn parse_declaration_specifiers(
+    tokens: &[Token],
+    pos: &mut usize,
+    definitions: &mut Vec<ParseNode>,
+    typedefs: &mut Typedefs,
+) -> Result<(StorageClass, CType), CompileError> {
+    parse_specifiers(tokens, pos, definitions, true, typedefs)
+}
+
+// Members, parameters and type names are declared without a storage class
+fn parse_specifier_qualifiers(
+    tokens: &[Token],
+    pos: &mut usize,
+    definitions: &mut Vec<ParseNode>,
+    typedefs: &mut Typedefs,
+) -> Result<CType, CompileError> {
+    let (_, ctype) = parse_specifiers(tokens, pos, definitions, false, typedefs)?;
+    Ok(ctype)
+}
+
+// Describes a struct or enum definition for an error about where it appears
+fn get_definition_name(definition: &ParseNode) -> String {
+    match &definition.node_type {
+        NodeType::StructDecl(ctype) => ctype.to_string(),
+        NodeType::EnumDecl(tag) => format!("enum {}", tag),
+        _ => panic!("{:?} is not a definition", definition),
+    }
+}
+
+// A type name, in a cast or sizeof, declares a type without naming an object. The
+// parentheses around it are consumed.
+fn parse_type_name(
+    tokens: &[Token],
+    pos: &mut usize,
+    typedefs: &mut Typedefs,
+) -> Result<CType, CompileError> {
+    verify_next_token!(tokens, pos, TokenKind::LParenthesis)?;
+    *pos += 1;
+
+    let mut definitions = vec![];
+    let ctype = parse_specifier_qualifiers(tokens, pos, &mut definitions, typedefs)?;
+    if let Some(definition) = definitions.first() {
+        return Err(CompileError::Parse(
+            definition.span.clone(),
+            format!(
+                "definition of '{}' in a type name is not supported",
+                get_definition_name(definition)
+            ),
+        ));
+    }
+
+    let (_, ctype) = parse_any_declarator(tokens, pos, ctype, DeclaratorKind::Abstract, typedefs)?;
+    verify_next_token!(tokens, pos, TokenKind::RParenthesis)?;
+
+    Ok(ctype)
+}
+
+// A struct or union specifier refers to a tag, and defines it when it is followed
+// by the declarations of the members. The type refers to the tag either way.
+fn parse_struct_specifier(
+    tokens: &[Token],
+    pos: &mut usize,
+    definitions: &mut Vec<ParseNode>,
+    typedefs: &mut Typedefs,
+) -> Result<CType, CompileError> {
+    let span = tokens.get_span(*pos);
+    let kind = match tokens.get_token(*pos)? {
+        TokenKind::Union => StructKind::Union,
+        _ => StructKind::Struct,
+    };
+
+    let tag = match tokens.get_token(*pos + 1)? {
+        TokenKind::Identifier(tag) => {
+            *pos += 1;
+            tag.clone()
+        }
+        TokenKind::LCurly => format!("(unnamed at {:?})", span),
+        token => {
+            return Err(CompileError::Parse(
+                tokens.get_span(*pos + 1),
+                format!("expected an identifier or '{{', found {:?}", token),
+            ))
+        }
+    };
+    let ctype = CType::Struct(Box::new(StructType::new(kind, tag)));
+
+    if !matches!(tokens.get_token(*pos + 1)?, TokenKind::LCurly) {
+        return Ok(ctype);
+    }
+    *pos += 1;
+
+    let mut definition_node = ParseNode::new(NodeType::StructDecl(ctype.clone()), span);
+    loop {
+        *pos += 1;
+        let member_type =
+            parse_specifier_qualifiers(tokens, pos, &mut definition_node.children, typedefs)?;
+
+        loop {
+            let (name, span, member_type) =
+                parse_declarator(tokens, pos, member_type.clone(), typedefs)?;
+            definition_node.children.push(ParseNode::new(
+                NodeType::MemberDecl(name, member_type),
+                span,
+            ));
+
+            match tokens.get_next_token(pos)? {
+                TokenKind::Comma => continue,
+                TokenKind::SemiColon => break,
+                token => {
+                    return Err(CompileError::Parse(
+                        tokens.get_span(*pos),
+                        format!("expected ',' or ';' after member, found {:?}", token),
+                    ))
+                }
+            }
+        }
+
+        if matches!(tokens.get_token(*pos + 1)?, TokenKind::RCurly) {
+            *pos += 1;
+            break;
+        }
+    }
+    definitions.push(definition_node);
+
+    Ok(ctype)
+}
+
+// An enum specifier defines its constants when it is followed by the list of them,
+// a constant without a value is one more than the one before it. The constants
+// and the enumerated type itself are ints.
+fn parse_enum_specifier(
+    tokens: &[Token],
+    pos: &mut usize,
+    definitions: &mut Vec<ParseNode>,
+    typedefs: &mut Typedefs,
+) -> Result<CType, CompileError> {
+    let span = tokens.get_span(*pos);
+
+    let tag = match tokens.get_token(*pos + 1)? {
+        TokenKind::Identifier(tag) => {
+            *pos += 1;
+            tag.clone()
+        }
+        TokenKind::LCurly => format!("(unnamed at {:?})", span),
+        token => {
+            return Err(CompileError::Parse(
+                tokens.get_span(*pos + 1),
+                format!("expected an identifier or '{{', found {:?}", token),
+            ))
+        }
+    };
+
+    if !matches!(tokens.get_token(*pos + 1)?, TokenKind::LCurly) {
+        return Ok(CType::Int);
+    }
+    *pos += 1;
+
+    let mut definition_node = ParseNode::new(NodeType::EnumDecl(tag), span);
+    loop {
+        let name = parse_identifier(tokens, pos)?;
+        let span = tokens.get_span(*pos);
+        typedefs.declare(&name, None, &span)?;
+
+        let mut enumerator_node = ParseNode::new(NodeType::Enumerator(name), span);
+        if matches!(tokens.get_token(*pos + 1)?, TokenKind::Assignment) {
+            *pos += 1;
+            enumerator_node
+                .children
+                .push(parse_conditional_expression(tokens, pos, typedefs)?);
+        }
+        definition_node.children.push(enumerator_node);
+
+        match tokens.get_next_token(pos)? {
+            TokenKind::Comma if matches!(tokens.get_token(*pos + 1)?, TokenKind::RCurly) => {
+                *pos += 1;
+                break;
+            }
+            TokenKind::Comma => continue,
+            TokenKind::RCurly => break,
+            token => {
+                return Err(CompileError::Parse(
+                    tokens.get_span(*pos),
+                    format!("expected ',' or '}}' after enumerator, found {:?}", token),
+                ))
+            }
+        }
+    }
+    definitions.push(definition_node);
+
+    Ok(CType::Int)
+}
+
+// A declarator of a function is followed by its parameters, after the name
+fn is_function_declarator(tokens: &[Token], pos: usize) -> Result<bool, CompileError> {
+    let mut pos = pos + 1;
+    while matches!(tokens.get_token(pos)?, TokenKind::Multiplication) {
+        pos += 1;
+    }
+
+    Ok(matches!(
+        tokens.get_token(pos + 1)?,
+        TokenKind::LParenthesis
+    ))
+}
+
+// A declaration without declarators only declares the tags and enumeration
+// constants it defines
+fn is_tag_declaration(tokens: &[Token], pos: usize) -> Result<bool, CompileError> {
+    Ok(matches!(tokens.get_token(pos + 1)?, TokenKind::SemiColon))
+}
+
+// Every `*` in front of a declarator adds a level of indirection to the type. The
+// qualifiers that follow it qualify the pointer, as in `char *const p`.
+fn parse_pointers(tokens: &[Token], pos: &mut usize, ctype: CType) -> Result<CType, CompileError> {
+    let mut ctype = ctype;
+
+    while matches!(tokens.get_token(*pos + 1)?, TokenKind::Multiplication) {
+        *pos += 1;
+        ctype = CType::Pointer(Box::new(ctype));
+
+        while is_type_qualifier(tokens.get_token(*pos + 1)?) {
+            *pos += 1;
+            if matches!(tokens.get_token(*pos)?, TokenKind::Const) {
+                ctype = ctype.to_const();
+            }
+        }
+    }
+
+    Ok(ctype)
+}
+
+// The lengths of an array declarator, `int a[2][3]` declares an array of 2 arrays
+// of 3 ints. The outermost length may be omitted when it follows from the initializer.
+// A length other than an integer literal is evaluated in the symbol pass.
+fn parse_array_lengths(
+    tokens: &[Token],
+    pos: &mut usize,
+    ctype: CType,
+    typedefs: &mut Typedefs,
+) -> Result<CType, CompileError> {
+    let mut lengths = vec![];
+
+    while matches!(tokens.get_token(*pos + 1)?, TokenKind::LBracket) {
+        *pos += 1;
+        if lengths.is_empty() && matches!(tokens.get_token(*pos + 1)?, TokenKind::RBracket) {
+            *pos += 1;
+            lengths.push(None);
+            continue;
+        }
+
+        let length = parse_conditional_expression(tokens, pos, typedefs)?;
+        verify_next_token!(tokens, pos, TokenKind::RBracket)?;
+        lengths.push(Some(length));
+    }
+
+    lengths
+        .into_iter()
+        .rev()
+        .try_fold(ctype, |element, length| match length {
+            None => Ok(CType::Array(Box::new(element), 0)),
+            Some(length) => match length.node_type {
+                NodeType::Const(value, _) if value > 0 => {
+                    Ok(CType::Array(Box::new(element), value as usize))
+                }
+                NodeType::Const(_, _) => Err(CompileError::Parse(
+                    length.span,
+                    "array length must be a positive integer constant".to_string(),
+                )),
+                _ => Ok(CType::UnevaluatedArray(Box::new(element), Box::new(length))),
+            },
+        })
+}
+
+// A declarator names the declared object, a parameter declarator may leave it
+// unnamed and the abstract declarator of a type name has no name
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+enum DeclaratorKind {
+    Named,
+    OptionallyNamed,
+    Abstract,
+}
+
+// A declarator in parentheses applies to the type after the array lengths that
+// follow it, `int (*p)[3]` declares a pointer to an array of 3 ints. Without a
+// name, parentheses that hold parameters would be a function declarator instead.
+fn is_nested_declarator(
+    tokens: &[Token],
+    pos: usize,
+    kind: DeclaratorKind,
+    typedefs: &Typedefs,
+) -> Result<bool, CompileError> {
+    if !matches!(tokens.get_token(pos + 1)?, TokenKind::LParenthesis) {
+        return Ok(false);
+    }
+
+    Ok(match (tokens.get_token(pos + 2)?, kind) {
+        (_, DeclaratorKind::Named) => true,
+        (TokenKind::Multiplication | TokenKind::LParenthesis, _) => true,
+        (TokenKind::Identifier(name), DeclaratorKind::OptionallyNamed) => {
+            typedefs.get_typedef(name).is_none()
+        }
+        _ => false,
+    })
+}
+
+// The position of the parenthesis that closes the one at pos
+fn find_closing_parenthesis(tokens: &[Token], pos: usize) -> Result<usize, CompileError> {
+    let mut pos = pos;
+    let mut depth = 0;
+
+    loop {
+        match tokens.get_token(pos)? {
+            TokenKind::LParenthesis => depth += 1,
+            TokenKind::RParenthesis if depth == 1 => return Ok(pos),
+            TokenKind::RParenthesis => depth -= 1,
+            _ => (),
+        }
+        pos += 1;
+    }
 }
 
-fn parse_function(tokens: &[Token], pos: &mut usize) -> Result<ParseNode, String> {
-    verify_token!(tokens, pos, Token::Int)?;
-    verify_next_token!(tokens, pos, Token::Identifier { .. })?;
-    verify_next_token!(tokens, pos, Token::LParenthesis)?;
-    verify_next_token!(tokens, pos, Token::RParenthesis)?;
-    verify_next_token!(tokens, pos, Token::LCurly)?;
+// Returns the name and its span, if the declarator has one, and the type of the
+// declarator. A nested declarator is parsed after the array lengths that follow it.
+fn parse_any_declarator(
+    tokens: &[Token],
+    pos: &mut usize,
+    ctype: CType,
+    kind: DeclaratorKind,
+    typedefs: &mut Typedefs,
+) -> Result<(Option<(String, Span)>, CType), CompileError> {
+    let ctype = parse_pointers(tokens, pos, ctype)?;
+
+    if is_nested_declarator(tokens, *pos, kind, typedefs)? {
+        let start = *pos + 1;
+        *pos = find_closing_parenthesis(tokens, start)?;
+        let ctype = parse_array_lengths(tokens, pos, ctype, typedefs)?;
+        let end = *pos;
 
-    let mut func_node = ParseNode::new(NodeType::Fn("main".to_string(), 0));
+        *pos = start;
+        let declarator = parse_any_declarator(tokens, pos, ctype, kind, typedefs)?;
+        verify_next_token!(tokens, pos, TokenKind::RParenthesis)?;
+        *pos = end;
+        return Ok(declarator);
+    }
 
-    while !matches!(tokens.get_token(*pos + 1)?, Token::RCurly) {
-        let stmt_node: ParseNode;
-        stmt_node = parse_statement(tokens, pos)?;
+    let name = match (tokens.get_token(*pos + 1)?, kind) {
+        (_, DeclaratorKind::Named)
+        | (TokenKind::Identifier(_), DeclaratorKind::OptionallyNamed) => {
+            let name = parse_identifier(tokens, pos)?;
+            Some((name, tokens.get_span(*pos)))
+        }
+        _ => None,
+    };
+    let ctype = parse_array_lengths(tokens, pos, ctype, typedefs)?;
+
+    Ok((name, ctype))
+}
+
+// Returns the name, its span and the type of the declarator
+fn parse_declarator(
+    tokens: &[Token],
+    pos: &mut usize,
+    ctype: CType,
+    typedefs: &mut Typedefs,
+) -> Result<(String, Span, CType), CompileError> {
+    let (name, ctype) = parse_any_declarator(tokens, pos, ctype, DeclaratorKind::Named, typedefs)?;
+    let (name, span) = name.expect("A named declarator has a name");
+
+    Ok((name, span, ctype))
+}
+
+// A function declared static is only visible in its file, others are global
+fn parse_function(
+    tokens: &[Token],
+    pos: &mut usize,
+    return_type: CType,
+    is_global: bool,
+    typedefs: &mut Typedefs,
+) -> Result<ParseNode, CompileError> {
+    let return_type = parse_pointers(tokens, pos, return_type)?;
+    let name = parse_identifier(tokens, pos)?;
+    let span = tokens.get_span(*pos);
+    typedefs.declare(&name, None, &span)?;
+    verify_next_token!(tokens, pos, TokenKind::LParenthesis)?;
+
+    // The parameters are in scope in the body of the function
+    typedefs.enter_scope();
+    let (mut params, is_variadic) = parse_parameters(tokens, pos, typedefs)?;
+
+    // A declaration only states the signature of a function defined elsewhere
+    if matches!(tokens.get_token(*pos + 1)?, TokenKind::SemiColon) {
+        *pos += 1;
+        typedefs.leave_scope();
+        let mut declaration_node = ParseNode::new(
+            NodeType::FnDecl(name, return_type, is_variadic, is_global),
+            span,
+        );
+        declaration_node.children.append(&mut params);
+        return Ok(declaration_node);
+    }
+
+    verify_next_token!(tokens, pos, TokenKind::LCurly)?;
+    if is_variadic {
+        return Err(CompileError::Parse(
+            span,
+            format!(
+                "definition of variadic function '{}' is not supported",
+                name
+            ),
+        ));
+    }
+
+    if let Some(param) = params
+        .iter()
+        .find(|param| matches!(&param.node_type, NodeType::FnParam(name, _, _) if name.is_empty()))
+    {
+        return Err(CompileError::Parse(
+            param.span.clone(),
+            "parameter name omitted".to_string(),
+        ));
+    }
+
+    let mut func_node = ParseNode::new(NodeType::Fn(name, 0, return_type, is_global), span);
+    func_node.children.append(&mut params);
+
+    while !matches!(tokens.get_token(*pos + 1)?, TokenKind::RCurly) {
+        let stmt_node = parse_statement(tokens, pos, typedefs)?;
         func_node.children.push(stmt_node);
     }
 
-    verify_next_token!(tokens, pos, Token::RCurly)?;
+    verify_next_token!(tokens, pos, TokenKind::RCurly)?;
+    typedefs.leave_scope();
+    verify_loop_control(&func_node, false, false)?;
 
     Ok(func_node)
 }
 
-fn parse_statement(tokens: &[Token], pos: &mut usize) -> Result<ParseNode, String> {
-    let token = tokens.get_next_token(pos)?;
+// Returns the parameters and whether the function is variadic, which is marked by
+// an ellipsis after the last parameter. The parameters of a declaration may be
+// unnamed, they are left with an empty name. A lone unnamed void parameter, as in
+// `int main(void)`, declares that there are none.
+fn parse_parameters(
+    tokens: &[Token],
+    pos: &mut usize,
+    typedefs: &mut Typedefs,
+) -> Result<(Vec<ParseNode>, bool), CompileError> {
+    let mut params = vec![];
 
-    match *token {
-        Token::Return => {
-            let exp_node = parse_expression(tokens, pos)?;
+    if matches!(tokens.get_token(*pos + 1)?, TokenKind::RParenthesis) {
+        *pos += 1;
+        return Ok((params, false));
+    }
+
+    loop {
+        *pos += 1;
+        let span = tokens.get_span(*pos);
+        let mut definitions = vec![];
+        let ctype = parse_specifier_qualifiers(tokens, pos, &mut definitions, typedefs)?;
+        if let Some(definition) = definitions.first() {
+            return Err(CompileError::Parse(
+                definition.span.clone(),
+                format!(
+                    "'{}' defined inside parameter list",
+                    get_definition_name(definition)
+                ),
+            ));
+        }
+
+        let (name, ctype) = parse_any_declarator(
+            tokens,
+            pos,
+            ctype,
+            DeclaratorKind::OptionallyNamed,
+            typedefs,
+        )?;
+        if ctype.is_void()
+            && name.is_none()
+            && params.is_empty()
+            && matches!(tokens.get_token(*pos + 1)?, TokenKind::RParenthesis)
+        {
+            *pos += 1;
+            return Ok((params, false));
+        }
+        let (name, span) = match name {
+            Some((name, span)) => {
+                typedefs.declare(&name, None, &span)?;
+                (name, span)
+            }
+            None => (String::new(), span),
+        };
+
+        // A parameter declared as an array is a pointer to its first element
+        let ctype = match ctype {
+            CType::Array(element, _) | CType::UnevaluatedArray(element, _) => {
+                CType::Pointer(element)
+            }
+            ctype => ctype,
+        };
+        params.push(ParseNode::new(NodeType::FnParam(name, 0, ctype), span));
+
+        match tokens.get_next_token(pos)? {
+            TokenKind::Comma if matches!(tokens.get_token(*pos + 1)?, TokenKind::Ellipsis) => {
+                *pos += 1;
+                verify_next_token!(tokens, pos, TokenKind::RParenthesis)?;
+                return Ok((params, true));
+            }
+            TokenKind::Comma => continue,
+            TokenKind::RParenthesis => return Ok((params, false)),
+            token => {
+                return Err(CompileError::Parse(
+                    tokens.get_span(*pos),
+                    format!("expected ',' or ')' after parameter, found {:?}", token),
+                ))
+            }
+        }
+    }
+}
+
+fn parse_statement(
+    tokens: &[Token],
+    pos: &mut usize,
+    typedefs: &mut Typedefs,
+) -> Result<ParseNode, CompileError> {
+    // Labels have a namespace of their own, so an identifier followed by a colon
+    // is a label even if it is a typedef name
+    if matches!(tokens.get_token(*pos + 1)?, TokenKind::Identifier(_))
+        && matches!(tokens.get_token(*pos + 2)?, TokenKind::Colon)
+    {
+        return parse_labeled_statement(tokens, pos, typedefs);
+    }
+
+    match tokens.get_token(*pos + 1)? {
+        TokenKind::Return => {
+            *pos += 1;
+            let span = tokens.get_span(*pos);
+            // A function returning void returns without a value
+            let mut children = vec![];
+            if !matches!(tokens.get_token(*pos + 1)?, TokenKind::SemiColon) {
+                children.push(parse_expression(tokens, pos, typedefs)?);
+            }
+            verify_next_token!(tokens, pos, TokenKind::SemiColon)?;
 
             Ok(ParseNode {
                 node_type: NodeType::Return,
-                children: vec![exp_node],
+                children,
+                span,
             })
         }
-        Token::Int => match tokens.get_next_token(pos)? {
-            Token::Identifier(x) => {
-                verify_next_token!(tokens, pos, Token::Assignment)?;
+        token if is_declaration_start(token, typedefs) => parse_declaration(tokens, pos, typedefs),
+        TokenKind::LCurly => parse_block(tokens, pos, typedefs),
+        TokenKind::If => parse_if(tokens, pos, typedefs),
+        TokenKind::While => parse_while(tokens, pos, typedefs),
+        TokenKind::Do => parse_do_while(tokens, pos, typedefs),
+        TokenKind::For => parse_for(tokens, pos, typedefs),
+        TokenKind::Switch => parse_switch(tokens, pos, typedefs),
+        TokenKind::Case | TokenKind::Default => parse_case(tokens, pos, typedefs),
+        TokenKind::Break => {
+            *pos += 1;
+            let span = tokens.get_span(*pos);
+            verify_next_token!(tokens, pos, TokenKind::SemiColon)?;
+            Ok(ParseNode::new(NodeType::Break, span))
+        }
+        TokenKind::Continue => {
+            *pos += 1;
+            let span = tokens.get_span(*pos);
+            verify_next_token!(tokens, pos, TokenKind::SemiColon)?;
+            Ok(ParseNode::new(NodeType::Continue, span))
+        }
+        TokenKind::Goto => {
+            *pos += 1;
+            let name = parse_identifier(tokens, pos)?;
+            let span = tokens.get_span(*pos);
+            verify_next_token!(tokens, pos, TokenKind::SemiColon)?;
+            Ok(ParseNode::new(NodeType::Goto(name), span))
+        }
+        TokenKind::SemiColon => {
+            *pos += 1;
+            Ok(ParseNode::new(NodeType::Empty, tokens.get_span(*pos)))
+        }
+        _ => {
+            let exp_node = parse_expression(tokens, pos, typedefs)?;
+            verify_next_token!(tokens, pos, TokenKind::SemiColon)?;
+            Ok(exp_node)
+        }
+    }
+}
 
-                let exp_node = parse_expression(tokens, pos)?;
+fn parse_declaration(
+    tokens: &[Token],
+    pos: &mut usize,
+    typedefs: &mut Typedefs,
+) -> Result<ParseNode, CompileError> {
+    *pos += 1;
+    let span = tokens.get_span(*pos);
+    let mut definitions = vec![];
+    let (storage_class, ctype) =
+        parse_declaration_specifiers(tokens, pos, &mut definitions, typedefs)?;
+    let declaration_node = ParseNode {
+        node_type: NodeType::Declaration(storage_class),
+        children: definitions,
+        span,
+    };
 
-                Ok(ParseNode {
-                    node_type: NodeType::VarDecl(x.clone(), 0),
-                    children: vec![exp_node],
-                })
+    if is_tag_declaration(tokens, *pos)? {
+        *pos += 1;
+        return Ok(declaration_node);
+    }
+
+    parse_init_declarators(tokens, pos, ctype, declaration_node, typedefs)
+}
+
+// Declares one or more variables, each with an optional initializer. The pointers
+// and array lengths belong to the declarator, so `int *p, q;` declares an int q.
+// The declarators of a typedef declare names for their types instead.
+fn parse_init_declarators(
+    tokens: &[Token],
+    pos: &mut usize,
+    ctype: CType,
+    declaration_node: ParseNode,
+    typedefs: &mut Typedefs,
+) -> Result<ParseNode, CompileError> {
+    let mut declaration_node = declaration_node;
+    let is_typedef = declaration_node.node_type == NodeType::Declaration(StorageClass::Typedef);
+
+    loop {
+        let (name, span, var_type) = parse_declarator(tokens, pos, ctype.clone(), typedefs)?;
+        let is_initialized = matches!(tokens.get_token(*pos + 1)?, TokenKind::Assignment);
+
+        if is_typedef {
+            if is_initialized {
+                return Err(CompileError::Parse(
+                    span,
+                    format!("typedef '{}' is initialized", name),
+                ));
             }
-            _ => Err(format!("No identifier found at {}.", pos)),
-        },
-        _ => Err(format!(
-            "Found statement with invalid starting token: {:?}",
-            token
-        )),
+            typedefs.declare(&name, Some(var_type), &span)?;
+        } else {
+            typedefs.declare(&name, None, &span)?;
+            let mut var_node = ParseNode::new(NodeType::VarDecl(name, 0, var_type), span);
+
+            if is_initialized {
+                *pos += 1;
+                var_node
+                    .children
+                    .push(parse_initializer(tokens, pos, typedefs)?);
+            }
+            declaration_node.children.push(var_node);
+        }
+
+        match tokens.get_next_token(pos)? {
+            TokenKind::Comma => continue,
+            TokenKind::SemiColon => return Ok(declaration_node),
+            token => {
+                return Err(CompileError::Parse(
+                    tokens.get_span(*pos),
+                    format!(
+                        "expected '=', ',' or ';' after declarator, found {:?}",
+                        token
+                    ),
+                ))
+            }
+        }
     }
 }
 
-fn get_precedence(token: &ParseNode) -> u32 {
-    match &token.node_type {
-        NodeType::UnaryOp(_) => 10,
-        NodeType::BinaryOp(x) => match x {
-            BinaryOp::Multiplication => 2,
-            BinaryOp::Plus | BinaryOp::Minus => 1,
-        },
-        _ => panic!("Could not get precedence of a non operator token"),
+// An expression, or a braced list of initializers which may end with a comma
+fn parse_initializer(
+    tokens: &[Token],
+    pos: &mut usize,
+    typedefs: &mut Typedefs,
+) -> Result<ParseNode, CompileError> {
+    if !matches!(tokens.get_token(*pos + 1)?, TokenKind::LCurly) {
+        return parse_assignment_expression(tokens, pos, typedefs);
+    }
+
+    *pos += 1;
+    let mut list_node = ParseNode::new(NodeType::InitializerList, tokens.get_span(*pos));
+
+    loop {
+        if matches!(tokens.get_token(*pos + 1)?, TokenKind::RCurly)
+            && !list_node.children.is_empty()
+        {
+            *pos += 1;
+            return Ok(list_node);
+        }
+
+        list_node
+            .children
+            .push(parse_initializer(tokens, pos, typedefs)?);
+
+        match tokens.get_next_token(pos)? {
+            TokenKind::Comma => continue,
+            TokenKind::RCurly => return Ok(list_node),
+            token => {
+                return Err(CompileError::Parse(
+                    tokens.get_span(*pos),
+                    format!(
+                        "expected ',' or '}}' in initializer list, found {:?}",
+                        token
+                    ),
+                ))
+            }
+        }
+    }
+}
+
+fn parse_block(
+    tokens: &[Token],
+    pos: &mut usize,
+    typedefs: &mut Typedefs,
+) -> Result<ParseNode, CompileError> {
+    verify_next_token!(tokens, pos, TokenKind::LCurly)?;
+
+    let mut block_node = ParseNode::new(NodeType::Block, tokens.get_span(*pos));
+
+    typedefs.enter_scope();
+    while !matches!(tokens.get_token(*pos + 1)?, TokenKind::RCurly) {
+        block_node
+            .children
+            .push(parse_statement(tokens, pos, typedefs)?);
+    }
+    typedefs.leave_scope();
+
+    verify_next_token!(tokens, pos, TokenKind::RCurly)?;
+
+    Ok(block_node)
+}
+
+fn parse_condition(
+    tokens: &[Token],
+    pos: &mut usize,
+    typedefs: &mut Typedefs,
+) -> Result<ParseNode, CompileError> {
+    verify_next_token!(tokens, pos, TokenKind::LParenthesis)?;
+    let condition = parse_expression(tokens, pos, typedefs)?;
+    verify_next_token!(tokens, pos, TokenKind::RParenthesis)?;
+
+    Ok(condition)
+}
+
+fn parse_if(
+    tokens: &[Token],
+    pos: &mut usize,
+    typedefs: &mut Typedefs,
+) -> Result<ParseNode, CompileError> {
+    verify_next_token!(tokens, pos, TokenKind::If)?;
+
+    let mut if_node = ParseNode::new(NodeType::If, tokens.get_span(*pos));
+    if_node
+        .children
+        .push(parse_condition(tokens, pos, typedefs)?);
+    if_node
+        .children
+        .push(parse_statement(tokens, pos, typedefs)?);
+
+    if matches!(
+        tokens.get(*pos + 1).map(|token| &token.kind),
+        Some(TokenKind::Else)
+    ) {
+        *pos += 1;
+        if_node
+            .children
+            .push(parse_statement(tokens, pos, typedefs)?);
     }
+
+    Ok(if_node)
+}
+
+fn parse_while(
+    tokens: &[Token],
+    pos: &mut usize,
+    typedefs: &mut Typedefs,
+) -> Result<ParseNode, CompileError> {
+    verify_next_token!(tokens, pos, TokenKind::While)?;
+
+    let mut while_node = ParseNode::new(NodeType::While, tokens.get_span(*pos));
+    while_node
+        .children
+        .push(parse_condition(tokens, pos, typedefs)?);
+    while_node
+        .children
+        .push(parse_statement(tokens, pos, typedefs)?);
+
+    Ok(while_node)
 }
 
-fn parse_output_stack(output_stack: &mut [ParseNode]) -> Result<ParseNode, String> {
-    let mut stack: Vec<ParseNode> = vec![];
+fn parse_do_while(
+    tokens: &[Token],
+    pos: &mut usize,
+    typedefs: &mut Typedefs,
+) -> Result<ParseNode, CompileError> {
+    verify_next_token!(tokens, pos, TokenKind::Do)?;
+
+    let mut do_while_node = ParseNode::new(NodeType::DoWhile, tokens.get_span(*pos));
+    do_while_node
+        .children
+        .push(parse_statement(tokens, pos, typedefs)?);
 
-    for node in output_stack {
-        if matches!(node.node_type, NodeType::UnaryOp(_)) {
-            node.children.push(stack.remove(stack.len() - 1));
-        } else if node.is_operation() {
-            node.children.push(stack.remove(stack.len() - 2));
-            node.children.push(stack.remove(stack.len() - 1));
+    verify_next_token!(tokens, pos, TokenKind::While)?;
+    do_while_node
+        .children
+        .push(parse_condition(tokens, pos, typedefs)?);
+    verify_next_token!(tokens, pos, TokenKind::SemiColon)?;
+
+    Ok(do_while_node)
+}
+
+// An omitted expression in a for statement, if the end token follows immediately
+fn parse_optional_expression(
+    tokens: &[Token],
+    pos: &mut usize,
+    typedefs: &mut Typedefs,
+) -> Result<ParseNode, CompileError> {
+    match tokens.get_token(*pos + 1)? {
+        TokenKind::SemiColon | TokenKind::RParenthesis => {
+            Ok(ParseNode::new(NodeType::Empty, tokens.get_span(*pos + 1)))
         }
+        _ => parse_expression(tokens, pos, typedefs),
+    }
+}
+
+fn parse_for(
+    tokens: &[Token],
+    pos: &mut usize,
+    typedefs: &mut Typedefs,
+) -> Result<ParseNode, CompileError> {
+    verify_next_token!(tokens, pos, TokenKind::For)?;
+    verify_next_token!(tokens, pos, TokenKind::LParenthesis)?;
 
-        stack.push(node.clone())
+    let mut for_node = ParseNode::new(NodeType::For, tokens.get_span(*pos));
+
+    // A variable declared in the for statement is in scope until the end of its body
+    typedefs.enter_scope();
+    if is_declaration_start(tokens.get_token(*pos + 1)?, typedefs) {
+        for_node
+            .children
+            .push(parse_declaration(tokens, pos, typedefs)?);
+    } else {
+        for_node
+            .children
+            .push(parse_optional_expression(tokens, pos, typedefs)?);
+        verify_next_token!(tokens, pos, TokenKind::SemiColon)?;
     }
 
-    Ok(stack.remove(0))
+    for_node
+        .children
+        .push(parse_optional_expression(tokens, pos, typedefs)?);
+    verify_next_token!(tokens, pos, TokenKind::SemiColon)?;
+    for_node
+        .children
+        .push(parse_optional_expression(tokens, pos, typedefs)?);
+    verify_next_token!(tokens, pos, TokenKind::RParenthesis)?;
+
+    for_node
+        .children
+        .push(parse_statement(tokens, pos, typedefs)?);
+    typedefs.leave_scope();
+
+    Ok(for_node)
+}
+
+// A label may also end a block, as in `cleanup: }`, where it labels an empty
+// statement
+fn parse_labeled_statement(
+    tokens: &[Token],
+    pos: &mut usize,
+    typedefs: &mut Typedefs,
+) -> Result<ParseNode, CompileError> {
+    let name = parse_identifier(tokens, pos)?;
+    let mut label_node = ParseNode::new(NodeType::Label(name), tokens.get_span(*pos));
+    verify_next_token!(tokens, pos, TokenKind::Colon)?;
+
+    let statement = match tokens.get_token(*pos + 1)? {
+        TokenKind::RCurly => ParseNode::new(NodeType::Empty, tokens.get_span(*pos)),
+        _ => parse_statement(tokens, pos, typedefs)?,
+    };
+    label_node.children.push(statement);
+
+    Ok(label_node)
+}
+
+// The body of a switch is usually a block, with the case labels in front of the
+// statements they jump to
+fn parse_switch(
+    tokens: &[Token],
+    pos: &mut usize,
+    typedefs: &mut Typedefs,
+) -> Result<ParseNode, CompileError> {
+    verify_next_token!(tokens, pos, TokenKind::Switch)?;
+
+    let mut switch_node = ParseNode::new(NodeType::Switch, tokens.get_span(*pos));
+    switch_node
+        .children
+        .push(parse_condition(tokens, pos, typedefs)?);
+    switch_node
+        .children
+        .push(parse_statement(tokens, pos, typedefs)?);
+
+    Ok(switch_node)
+}
+
+// A case label, with its constant value, or the default label, followed by the
+// statement it labels
+fn parse_case(
+    tokens: &[Token],
+    pos: &mut usize,
+    typedefs: &mut Typedefs,
+) -> Result<ParseNode, CompileError> {
+    *pos += 1;
+    let span = tokens.get_span(*pos);
+
+    let mut case_node = match tokens.get_token(*pos)? {
+        TokenKind::Case => {
+            let mut case_node = ParseNode::new(NodeType::Case, span);
+            case_node
+                .children
+                .push(parse_conditional_expression(tokens, pos, typedefs)?);
+            case_node
+        }
+        _ => ParseNode::new(NodeType::Default, span),
+    };
+    verify_next_token!(tokens, pos, TokenKind::Colon)?;
+    case_node
+        .children
+        .push(parse_statement(tokens, pos, typedefs)?);
+
+    Ok(case_node)
 }
 
-fn convert_token_to_value(token: &Token) -> Result<NodeType, String> {
+// Continue may only appear within the body of a loop, case labels only within the
+// body of a switch and break within either
+fn verify_loop_control(
+    node: &ParseNode,
+    in_loop: bool,
+    in_switch: bool,
+) -> Result<(), CompileError> {
+    let message = match node.node_type {
+        NodeType::Break if !in_loop && !in_switch => "break statement not within loop or switch",
+        NodeType::Continue if !in_loop => "continue statement not within a loop",
+        NodeType::Case if !in_switch => "case label not within a switch statement",
+        NodeType::Default if !in_switch => "'default' label not within a switch statement",
+        _ => "",
+    };
+    if !message.is_empty() {
+        return Err(CompileError::Parse(node.span.clone(), message.to_string()));
+    }
+
+    let in_loop = in_loop
+        || matches!(
+            node.node_type,
+            NodeType::While | NodeType::DoWhile | NodeType::For
+        );
+    let in_switch = in_switch || node.node_type == NodeType::Switch;
+    for child in node.children.iter() {
+        verify_loop_control(child, in_loop, in_switch)?;
+    }
+
+    Ok(())
+}
+
+// Higher binds tighter, following the C operator precedence table
+fn get_precedence(operation: &BinaryOp) -> u32 {
+    match operation {
+        BinaryOp::Multiplication | BinaryOp::Division | BinaryOp::Modulo => 10,
+        BinaryOp::Plus | BinaryOp::Minus => 9,
+        BinaryOp::ShiftLeft | BinaryOp::ShiftRight => 8,
+        BinaryOp::Less | BinaryOp::LessOrEqual | BinaryOp::Greater | BinaryOp::GreaterOrEqual => 7,
+        BinaryOp::Equal | BinaryOp::NotEqual => 6,
+        BinaryOp::BitwiseAnd => 5,
+        BinaryOp::BitwiseXor => 4,
+        BinaryOp::BitwiseOr => 3,
+        BinaryOp::LogicalAnd => 2,
+        BinaryOp::LogicalOr => 1,
+    }
+}
+
+fn get_unary_op_from_token(token: &TokenKind) -> Option<UnaryOp> {
     match token {
-        Token::Integer(x) => Ok(NodeType::Const(*x)),
-        _ => Err(format!(
-            "The token {:?} cannot be converted to a unary operation.",
-            token
-        )),
+        TokenKind::Plus => Some(UnaryOp::Plus),
+        TokenKind::Minus => Some(UnaryOp::Minus),
+        TokenKind::LogicalNot => Some(UnaryOp::LogicalNot),
+        TokenKind::BitwiseNot => Some(UnaryOp::BitwiseNot),
+        TokenKind::BitwiseAnd => Some(UnaryOp::AddressOf),
+        TokenKind::Multiplication => Some(UnaryOp::Dereference),
+        _ => None,
     }
 }
 
-fn get_unary_op_from_token(token: &Token) -> Result<UnaryOp, String> {
+fn get_binary_op_from_token(token: &TokenKind) -> Option<BinaryOp> {
     match token {
-        Token::Minus => Ok(UnaryOp::Minus),
-        Token::Identifier(x) => Ok(UnaryOp::Function(x.clone())),
-        _ => Err(format!(
-            "The token {:?} cannot be converted to a unary operation.",
-            token
-        )),
+        TokenKind::Minus => Some(BinaryOp::Minus),
+        TokenKind::Plus => Some(BinaryOp::Plus),
+        TokenKind::Multiplication => Some(BinaryOp::Multiplication),
+        TokenKind::Division => Some(BinaryOp::Division),
+        TokenKind::Modulo => Some(BinaryOp::Modulo),
+        TokenKind::Less => Some(BinaryOp::Less),
+        TokenKind::LessOrEqual => Some(BinaryOp::LessOrEqual),
+        TokenKind::Greater => Some(BinaryOp::Greater),
+        TokenKind::GreaterOrEqual => Some(BinaryOp::GreaterOrEqual),
+        TokenKind::Equal => Some(BinaryOp::Equal),
+        TokenKind::NotEqual => Some(BinaryOp::NotEqual),
+        TokenKind::LogicalAnd => Some(BinaryOp::LogicalAnd),
+        TokenKind::LogicalOr => Some(BinaryOp::LogicalOr),
+        TokenKind::BitwiseAnd => Some(BinaryOp::BitwiseAnd),
+        TokenKind::BitwiseOr => Some(BinaryOp::BitwiseOr),
+        TokenKind::BitwiseXor => Some(BinaryOp::BitwiseXor),
+        TokenKind::ShiftLeft => Some(BinaryOp::ShiftLeft),
+        TokenKind::ShiftRight => Some(BinaryOp::ShiftRight),
+        _ => None,
     }
 }
 
-fn get_binary_op_from_token(token: &Token) -> Result<BinaryOp, String> {
+fn get_assignment_from_token(token: &TokenKind) -> Option<NodeType> {
     match token {
-        Token::Minus => Ok(BinaryOp::Minus),
-        Token::Plus => Ok(BinaryOp::Plus),
-        Token::Multiplication => Ok(BinaryOp::Multiplication),
-        _ => Err(format!(
-            "The token {:?} cannot be converted to a binary operation.",
-            token
+        TokenKind::Assignment => Some(NodeType::Assignment),
+        TokenKind::PlusAssignment => Some(NodeType::CompoundAssignment(BinaryOp::Plus)),
+        TokenKind::MinusAssignment => Some(NodeType::CompoundAssignment(BinaryOp::Minus)),
+        TokenKind::MultiplicationAssignment => {
+            Some(NodeType::CompoundAssignment(BinaryOp::Multiplication))
+        }
+        TokenKind::DivisionAssignment => Some(NodeType::CompoundAssignment(BinaryOp::Division)),
+        TokenKind::ModuloAssignment => Some(NodeType::CompoundAssignment(BinaryOp::Modulo)),
+        TokenKind::ShiftLeftAssignment => Some(NodeType::CompoundAssignment(BinaryOp::ShiftLeft)),
+        TokenKind::ShiftRightAssignment => Some(NodeType::CompoundAssignment(BinaryOp::ShiftRight)),
+        TokenKind::BitwiseAndAssignment => Some(NodeType::CompoundAssignment(BinaryOp::BitwiseAnd)),
+        TokenKind::BitwiseOrAssignment => Some(NodeType::CompoundAssignment(BinaryOp::BitwiseOr)),
+        TokenKind::BitwiseXorAssignment => Some(NodeType::CompoundAssignment(BinaryOp::BitwiseXor)),
+        _ => None,
+    }
+}
+
+fn parse_call_arguments(
+    tokens: &[Token],
+    pos: &mut usize,
+    typedefs: &mut Typedefs,
+) -> Result<Vec<ParseNode>, CompileError> {
+    verify_next_token!(tokens, pos, TokenKind::LParenthesis)?;
+    let mut arguments = vec![];
+
+    if matches!(tokens.get_token(*pos + 1)?, TokenKind::RParenthesis) {
+        *pos += 1;
+        return Ok(arguments);
+    }
+
+    loop {
+        arguments.push(parse_assignment_expression(tokens, pos, typedefs)?);
+
+        match tokens.get_next_token(pos)? {
+            TokenKind::Comma => continue,
+            TokenKind::RParenthesis => return Ok(arguments),
+            token => {
+                return Err(CompileError::Parse(
+                    tokens.get_span(*pos),
+                    format!("expected ',' or ')' after argument, found {:?}", token),
+                ))
+            }
+        }
+    }
+}
+
+fn parse_primary_expression(
+    tokens: &[Token],
+    pos: &mut usize,
+    typedefs: &mut Typedefs,
+) -> Result<ParseNode, CompileError> {
+    let token = tokens.get_next_token(pos)?;
+    let span = tokens.get_span(*pos);
+
+    match token {
+        TokenKind::Integer(x, ctype) => Ok(ParseNode::new(
+            NodeType::Const(*x as i64, ctype.clone()),
+            span,
+        )),
+        // A character constant has type int, and a plain char is signed on x86-64
+        TokenKind::Character(x) => Ok(ParseNode::new(
+            NodeType::Const(*x as i8 as i64, CType::Int),
+            span,
+        )),
+        TokenKind::Identifier(name) => Ok(ParseNode::new(
+            NodeType::Var(name.clone(), 0, CType::Int),
+            span,
+        )),
+        // Adjacent string literals are concatenated into one
+        TokenKind::StringLiteral(bytes) => {
+            let mut bytes = bytes.clone();
+            while let TokenKind::StringLiteral(next) = tokens.get_token(*pos + 1)? {
+                bytes.extend_from_slice(next);
+                *pos += 1;
+            }
+            Ok(ParseNode::new(NodeType::StringLiteral(bytes), span))
+        }
+        TokenKind::LParenthesis => {
+            let exp_node = parse_expression(tokens, pos, typedefs)?;
+            verify_next_token!(tokens, pos, TokenKind::RParenthesis)?;
+            Ok(exp_node)
+        }
+        token => Err(CompileError::Parse(
+            span,
+            format!("expected an expression, found {:?}", token),
         )),
     }
 }
 
-fn process_operation(
-    token: &Token,
-    output_stack: &mut Vec<ParseNode>,
-    operator_stack: &mut Vec<ParseNode>,
-    previous_was_value: &mut bool,
-) -> Result<(), String> {
-    let node: ParseNode;
+fn parse_postfix_expression(
+    tokens: &[Token],
+    pos: &mut usize,
+    typedefs: &mut Typedefs,
+) -> Result<ParseNode, CompileError> {
+    let mut node = parse_primary_expression(tokens, pos, typedefs)?;
 
-    if !*previous_was_value {
-        node = ParseNode::new(NodeType::UnaryOp(get_unary_op_from_token(token)?));
-    } else {
-        node = ParseNode::new(NodeType::BinaryOp(get_binary_op_from_token(token)?));
+    loop {
+        match (
+            tokens.get(*pos + 1).map(|token| &token.kind),
+            &node.node_type,
+        ) {
+            (Some(TokenKind::LParenthesis), NodeType::Var(name, _, _)) => {
+                let mut call_node = ParseNode::new(
+                    NodeType::FnCall(name.clone(), CType::Int, false),
+                    node.span.clone(),
+                );
+                call_node.children = parse_call_arguments(tokens, pos, typedefs)?;
+                node = call_node;
+            }
+            (Some(TokenKind::LParenthesis), _) => {
+                return Err(CompileError::Parse(
+                    node.span.clone(),
+                    "called object is not a function".to_string(),
+                ))
+            }
+            // a[i] is *(a + i)
+            (Some(TokenKind::LBracket), _) => {
+                *pos += 1;
+                let span = tokens.get_span(*pos);
+                let index_node = parse_expression(tokens, pos, typedefs)?;
+                verify_next_token!(tokens, pos, TokenKind::RBracket)?;
 
-        let previous_operator_precedence = match operator_stack.first() {
-            Some(x) => get_precedence(x),
-            None => 0,
-        };
-        if get_precedence(&node) < previous_operator_precedence {
-            // TODO FIX: left associative should take precedence if precedence is equal
-            output_stack.append(operator_stack);
+                let addition_node = ParseNode {
+                    node_type: NodeType::BinaryOp(BinaryOp::Plus),
+                    children: vec![node, index_node],
+                    span: span.clone(),
+                };
+                node = ParseNode {
+                    node_type: NodeType::UnaryOp(UnaryOp::Dereference),
+                    children: vec![addition_node],
+                    span,
+                };
+            }
+            // a->b is (*a).b
+            (Some(TokenKind::Dot | TokenKind::Arrow), _) => {
+                *pos += 1;
+                let span = tokens.get_span(*pos);
+                if matches!(tokens.get_token(*pos)?, TokenKind::Arrow) {
+                    node = ParseNode {
+                        node_type: NodeType::UnaryOp(UnaryOp::Dereference),
+                        children: vec![node],
+                        span: span.clone(),
+                    };
+                }
+
+                let name = parse_identifier(tokens, pos)?;
+                node = ParseNode {
+                    node_type: NodeType::Member(name, 0, CType::Int),
+                    children: vec![node],
+                    span,
+                };
+            }
+            (Some(TokenKind::Increment | TokenKind::Decrement), _) => {
+                *pos += 1;
+                let node_type = match tokens.get_token(*pos)? {
+                    TokenKind::Increment => NodeType::PostIncrement,
+                    _ => NodeType::PostDecrement,
+                };
+
+                node = ParseNode {
+                    node_type,
+                    children: vec![node],
+                    span: tokens.get_span(*pos),
+                };
+            }
+            _ => return Ok(node),
         }
     }
+}
 
-    (*operator_stack).insert(0, node); // Change vec to stack
-    *previous_was_value = false;
+// Prefix operators and casts are right associative, so the operand is parsed
+// recursively. A parenthesis that starts a type name is a cast, rather than an
+// expression in parentheses.
+fn parse_unary_expression(
+    tokens: &[Token],
+    pos: &mut usize,
+    typedefs: &mut Typedefs,
+) -> Result<ParseNode, CompileError> {
+    let is_type_name = |offset| match tokens.get_token(*pos + offset) {
+        Ok(TokenKind::LParenthesis) => tokens
+            .get_token(*pos + offset + 1)
+            .is_ok_and(|token| is_type_name_start(token, typedefs)),
+        _ => false,
+    };
 
-    Ok(())
+    if is_type_name(1) {
+        let span = tokens.get_span(*pos + 1);
+        let ctype = parse_type_name(tokens, pos, typedefs)?;
+
+        return Ok(ParseNode {
+            node_type: NodeType::Cast(ctype),
+            children: vec![parse_unary_expression(tokens, pos, typedefs)?],
+            span,
+        });
+    }
+
+    if matches!(tokens.get_token(*pos + 1)?, TokenKind::Sizeof) && is_type_name(2) {
+        *pos += 1;
+        let span = tokens.get_span(*pos);
+        let ctype = parse_type_name(tokens, pos, typedefs)?;
+
+        return Ok(ParseNode {
+            node_type: NodeType::SizeOf,
+            children: vec![ParseNode::new(NodeType::TypeName(ctype), span.clone())],
+            span,
+        });
+    }
+
+    let node_type = match tokens.get_token(*pos + 1)? {
+        TokenKind::Increment => NodeType::PreIncrement,
+        TokenKind::Decrement => NodeType::PreDecrement,
+        TokenKind::Sizeof => NodeType::SizeOf,
+        token => match get_unary_op_from_token(token) {
+            Some(operation) => NodeType::UnaryOp(operation),
+            None => return parse_postfix_expression(tokens, pos, typedefs),
+        },
+    };
+    *pos += 1;
+    let span = tokens.get_span(*pos);
+
+    Ok(ParseNode {
+        node_type,
+        children: vec![parse_unary_expression(tokens, pos, typedefs)?],
+        span,
+    })
 }
 
-fn process_value(
-    token: &Token,
-    output_stack: &mut Vec<ParseNode>,
-    previous_was_value: &mut bool,
-) -> Result<(), String> {
-    let node: ParseNode;
+// Precedence climbing: only operators binding at least as tight as min_precedence
+// are consumed, requiring a strictly higher precedence on the right side makes
+// the binary operators left associative
+fn parse_binary_expression(
+    tokens: &[Token],
+    pos: &mut usize,
+    min_precedence: u32,
+    typedefs: &mut Typedefs,
+) -> Result<ParseNode, CompileError> {
+    let mut node = parse_unary_expression(tokens, pos, typedefs)?;
 
-    if matches!(token, Token::Identifier { .. }) {
-        node = ParseNode::new(NodeType::Var(token.get_string_value().clone(), 0));
-    } else {
-        node = ParseNode::new(convert_token_to_value(&token.clone())?);
+    while let Some(operation) = tokens
+        .get(*pos + 1)
+        .map(|token| &token.kind)
+        .and_then(get_binary_op_from_token)
+    {
+        let precedence = get_precedence(&operation);
+        if precedence < min_precedence {
+            break;
+        }
+        *pos += 1;
+        let span = tokens.get_span(*pos);
+
+        let right_node = parse_binary_expression(tokens, pos, precedence + 1, typedefs)?;
+        node = ParseNode {
+            node_type: NodeType::BinaryOp(operation),
+            children: vec![node, right_node],
+            span,
+        };
     }
 
-    output_stack.push(node);
-    *previous_was_value = true;
+    Ok(node)
+}
 
-    Ok(())
+// The conditional operator is right associative, so a ? b : c ? d : e is
+// a ? b : (c ? d : e). Any expression fits between the '?' and the ':'.
+fn parse_conditional_expression(
+    tokens: &[Token],
+    pos: &mut usize,
+    typedefs: &mut Typedefs,
+) -> Result<ParseNode, CompileError> {
+    let condition = parse_binary_expression(tokens, pos, 0, typedefs)?;
+
+    if !matches!(tokens.get_token(*pos + 1)?, TokenKind::Question) {
+        return Ok(condition);
+    }
+    *pos += 1;
+    let span = tokens.get_span(*pos);
+
+    let true_value = parse_expression(tokens, pos, typedefs)?;
+    verify_next_token!(tokens, pos, TokenKind::Colon)?;
+    let false_value = parse_conditional_expression(tokens, pos, typedefs)?;
+
+    Ok(ParseNode {
+        node_type: NodeType::Conditional,
+        children: vec![condition, true_value, false_value],
+        span,
+    })
 }
 
-// Source: https://en.wikipedia.org/wiki/Shunting_yard_algorithm
-fn parse_expression(tokens: &[Token], pos: &mut usize) -> Result<ParseNode, String> {
-    let mut output_stack: Vec<ParseNode> = vec![];
-    let mut operator_stack: Vec<ParseNode> = vec![];
-    let mut previous_was_value: bool = false;
+// Assignments bind weaker than all but the comma and are right associative, so
+// the value is parsed recursively. Whether the target is an lvalue is checked in
+// the symbol pass.
+fn parse_assignment_expression(
+    tokens: &[Token],
+    pos: &mut usize,
+    typedefs: &mut Typedefs,
+) -> Result<ParseNode, CompileError> {
+    let node = parse_conditional_expression(tokens, pos, typedefs)?;
 
-    let mut token = tokens.get_next_token(pos)?;
-    while !matches!(token, Token::SemiColon) {
-        let mut is_function: bool = false;
+    match tokens
+        .get(*pos + 1)
+        .map(|token| &token.kind)
+        .and_then(get_assignment_from_token)
+    {
+        Some(node_type) => {
+            *pos += 1;
+            let span = tokens.get_span(*pos);
 
-        if matches!(token, Token::Identifier { .. }) {
-            is_function = matches!(tokens.get_token(*pos + 1)?, Token::LParenthesis);
+            Ok(ParseNode {
+                node_type,
+                children: vec![node, parse_assignment_expression(tokens, pos, typedefs)?],
+                span,
+            })
         }
+        None => Ok(node),
+    }
+}
 
-        if is_function || token.is_operation() {
-            process_operation(
-                token,
-                &mut output_stack,
-                &mut operator_stack,
-                &mut previous_was_value,
-            )?;
-        } else {
-            process_value(token, &mut output_stack, &mut previous_was_value)?;
-        }
+// Expressions separated by the comma operator, which is left associative. Leaves
+// the token that ends the expression for the caller to consume.
+fn parse_expression(
+    tokens: &[Token],
+    pos: &mut usize,
+    typedefs: &mut Typedefs,
+) -> Result<ParseNode, CompileError> {
+    let mut node = parse_assignment_expression(tokens, pos, typedefs)?;
 
-        token = tokens.get_next_token(pos)?;
-    }
+    while matches!(tokens.get_token(*pos + 1)?, TokenKind::Comma) {
+        *pos += 1;
+        let span = tokens.get_span(*pos);
 
-    output_stack.append(&mut operator_stack);
+        node = ParseNode {
+            node_type: NodeType::Comma,
+            children: vec![node, parse_assignment_expression(tokens, pos, typedefs)?],
+            span,
+        };
+    }
 
-    Ok(parse_output_stack(&mut output_stack)?)
+    Ok(node)
 }
 
-pub fn parse_tokens(tokens: Vec<Token>) -> Result<ParseNode, String> {
-    let mut pos = 0;
-    parse_function(&tokens, &mut pos).and_then(|node| {
-        if (1 + pos) == tokens.len() {
-            Ok(node)
+fn parse_program(
+    tokens: &[Token],
+    pos: &mut usize,
+    typedefs: &mut Typedefs,
+) -> Result<ParseNode, CompileError> {
+    let mut prog_node = ParseNode::new(NodeType::Prog("program".to_string()), tokens.get_span(0));
+
+    // The structs defined in the return type of a function are declared in front of it
+    while !matches!(tokens.get_token(*pos)?, TokenKind::EndOfFile) {
+        let span = tokens.get_span(*pos);
+        let mut definitions = vec![];
+        let (storage_class, ctype) =
+            parse_declaration_specifiers(tokens, pos, &mut definitions, typedefs)?;
+        let is_global = storage_class != StorageClass::Static;
+        let declaration_node = ParseNode {
+            node_type: NodeType::Declaration(storage_class),
+            children: definitions,
+            span,
+        };
+
+        if is_tag_declaration(tokens, *pos)? {
+            *pos += 1;
+            prog_node.children.push(declaration_node);
+        } else if is_function_declarator(tokens, *pos)? {
+            if declaration_node.node_type == NodeType::Declaration(StorageClass::Typedef) {
+                return Err(CompileError::Parse(
+                    declaration_node.span,
+                    "typedef of a function type is not supported".to_string(),
+                ));
+            }
+            if !declaration_node.children.is_empty() {
+                prog_node.children.push(declaration_node);
+            }
+            prog_node
+                .children
+                .push(parse_function(tokens, pos, ctype, is_global, typedefs)?);
         } else {
-            Err(format!(
-                "Parsing Error: Expected end of input, found {:?} at {}",
-                &tokens[pos], pos
-            ))
+            let declaration_node =
+                parse_init_declarators(tokens, pos, ctype, declaration_node, typedefs)?;
+            prog_node.children.push(declaration_node);
         }
-    })
+        *pos += 1;
+    }
+
+    Ok(prog_node)
+}
+
+pub fn parse_tokens(tokens: Vec<Token>) -> Result<ParseNode, CompileError> {
+    let mut pos = 0;
+    parse_program(&tokens, &mut pos, &mut Typedefs::new())
 }
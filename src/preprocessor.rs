@@ -0,0 +1,1378 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::error::CompileError;
+use crate::lexer::{lex_character_constant, lex_integer, TokenKind};
+use crate::source::{format_line_marker, span_between, SourceFile, Span};
+
+const MAX_INCLUDE_DEPTH: usize = 200;
+
+// Blank lines of the output up to which the lines are kept in sync with newlines,
+// rather than with a line marker
+const MAX_BLANK_LINES: usize = 8;
+
+const PUNCTUATORS: [&str; 48] = [
+    "...", "<<=", ">>=", "->", "++", "--", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "*=",
+    "/=", "%=", "+=", "-=", "&=", "^=", "|=", "##", "#", "[", "]", "(", ")", "{", "}", ".", "&",
+    "*", "+", "-", "~", "!", "/", "%", "<", ">", "^", "|", "?", ":", ";", "=", ",",
+];
+
+// A line of the file, after joining lines ended by a backslash and replacing
+// comments with a space
+struct Line {
+    text: String,
+    offsets: Vec<usize>, // offset in the file of every byte of the text, and of its end
+}
+
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+enum PpTokenKind {
+    Identifier,
+    Number,
+    Character,
+    String,
+    Punctuator,
+    Other,       // any other character, such as a stray backslash
+    Placemarker, // an empty argument, next to a ## operator
+}
+
+#[derive(Clone, Debug)]
+struct PpToken {
+    kind: PpTokenKind,
+    text: String,
+    has_space: bool, // whitespace comes before the token
+    span: Span,
+    is_from_source: bool, // written in the file, rather than produced by a macro
+    column: usize,        // column in the file, of the macro invocation for a token it produced
+    hide_set: Vec<String>, // macros that may not be expanded again in the token
+}
+
+impl PpToken {
+    fn is_punctuator(&self, text: &str) -> bool {
+        self.kind == PpTokenKind::Punctuator && self.text == text
+    }
+}
+
+struct Macro {
+    parameters: Option<Vec<String>>, // None for an object-like macro
+    is_variadic: bool,               // the last parameter is __VA_ARGS__
+    body: Vec<PpToken>,
+}
+
+impl Macro {
+    // Whether a redefinition is allowed, it has to be the same up to whitespace
+    fn is_same_as(&self, other: &Macro) -> bool {
+        self.parameters == other.parameters
+            && self.is_variadic == other.is_variadic
+            && self.body.len() == other.body.len()
+            && self
+                .body
+                .iter()
+                .zip(&other.body)
+                .all(|(a, b)| a.text == b.text && a.has_space == b.has_space)
+    }
+}
+
+struct Conditional {
+    span: Span,      // span of the directive that opened it
+    is_active: bool, // whether the lines of the current group are kept
+    was_taken: bool, // whether any group was kept so far
+    has_else: bool,
+}
+
+// The preprocessed text, with the line of the source file that the current line
+// of the text comes from
+struct Output {
+    text: String,
+    name: String,
+    line: usize,
+    column: usize,                // characters written on the current line
+    marker_start: Option<usize>,  // offset of a line marker that nothing follows yet
+    columns: Vec<(usize, usize)>, // offset of every token, and its column in the file
+}
+
+impl Output {
+    fn newline(&mut self) {
+        self.marker_start = None;
+        self.text.push('\n');
+        self.line += 1;
+        self.column = 0;
+    }
+
+    fn mark(&mut self, line: usize, name: &str) {
+        if self.column > 0 {
+            self.newline();
+        }
+        // A marker that nothing follows is replaced
+        if let Some(start) = self.marker_start {
+            self.text.truncate(start);
+        }
+        self.marker_start = Some(self.text.len());
+        self.text.push_str(&format_line_marker(line, name));
+        self.text.push('\n');
+        self.name = name.to_string();
+        self.line = line;
+    }
+
+    fn move_to_line(&mut self, line: usize) {
+        if line > self.line + MAX_BLANK_LINES {
+            let name = self.name.clone();
+            self.mark(line, &name);
+        }
+        while self.line < line {
+            self.newline();
+        }
+    }
+
+    fn push_str(&mut self, s: &str) {
+        self.marker_start = None;
+        self.text.push_str(s);
+        self.column += s.chars().count();
+    }
+}
+
+struct Preprocessor<'a> {
+    include_paths: &'a [String],
+    macros: HashMap<String, Macro>,
+    sources: Vec<Rc<SourceFile>>,
+    output: Output,
+    line: usize, // line that is being expanded, for __LINE__
+    depth: usize,
+}
+
+// Joins lines ended by a backslash and replaces comments with a space, keeping
+// the offsets of the bytes that remain
+fn split_lines(file: &Rc<SourceFile>) -> Result<Vec<Line>, CompileError> {
+    let content = file.content.as_bytes();
+    let mut bytes: Vec<(u8, usize)> = vec![];
+    let mut i = 0;
+    while i < content.len() {
+        let rest = &content[i..];
+        if rest.starts_with(b"\\\n") {
+            i += 2;
+        } else if rest.starts_with(b"\\\r\n") {
+            i += 3;
+        } else {
+            bytes.push((content[i], i));
+            i += 1;
+        }
+    }
+
+    let mut lines = vec![];
+    let mut text = vec![];
+    let mut offsets = vec![];
+    let mut quote: Option<u8> = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        let (c, offset) = bytes[i];
+        let next = bytes.get(i + 1).map(|&(c, _)| c);
+
+        match (quote, c, next) {
+            (_, b'\n', _) => {
+                offsets.push(offset);
+                lines.push(Line {
+                    text: String::from_utf8(std::mem::take(&mut text)).unwrap(),
+                    offsets: std::mem::take(&mut offsets),
+                });
+                quote = None;
+                i += 1;
+            }
+            (Some(_), b'\\', Some(escaped)) if escaped != b'\n' => {
+                text.extend([c, escaped]);
+                offsets.extend([offset, bytes[i + 1].1]);
+                i += 2;
+            }
+            (Some(q), _, _) => {
+                if c == q {
+                    quote = None;
+                }
+                text.push(c);
+                offsets.push(offset);
+                i += 1;
+            }
+            (None, b'"' | b'\'', _) => {
+                quote = Some(c);
+                text.push(c);
+                offsets.push(offset);
+                i += 1;
+            }
+            (None, b'/', Some(b'/')) => {
+                while i < bytes.len() && bytes[i].0 != b'\n' {
+                    i += 1;
+                }
+            }
+            (None, b'/', Some(b'*')) => {
+                let end = (i + 2..bytes.len())
+                    .find(|&j| {
+                        bytes[j].0 == b'*' && bytes.get(j + 1).map(|&(c, _)| c) == Some(b'/')
+                    })
+                    .ok_or_else(|| {
+                        CompileError::Preprocess(
+                            span_between(file, offset, bytes[i + 1].1 + 1),
+                            "unterminated comment".to_string(),
+                        )
+                    })?;
+                text.push(b' ');
+                offsets.push(offset);
+                i = end + 2;
+            }
+            _ => {
+                text.push(c);
+                offsets.push(offset);
+                i += 1;
+            }
+        }
+    }
+
+    if !text.is_empty() {
+        offsets.push(content.len());
+        lines.push(Line {
+            text: String::from_utf8(text).unwrap(),
+            offsets,
+        });
+    }
+
+    Ok(lines)
+}
+
+// Splits text into preprocessing tokens, span_of gives the span of the bytes from
+// start up to end. A quote without its closing quote is a token of its own, for
+// the lexer to report if the line is not skipped.
+fn tokenize_text(text: &str, span_of: impl Fn(usize, usize) -> Span) -> Vec<PpToken> {
+    let bytes = text.as_bytes();
+    let mut tokens = vec![];
+    let mut has_space = false;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let c = bytes[i];
+        let start = i;
+
+        let kind = if c.is_ascii_whitespace() {
+            has_space = true;
+            i += 1;
+            continue;
+        } else if c.is_ascii_alphabetic() || c == b'_' {
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                i += 1;
+            }
+            PpTokenKind::Identifier
+        } else if c.is_ascii_digit()
+            || (c == b'.' && bytes.get(i + 1).is_some_and(u8::is_ascii_digit))
+        {
+            i += 1;
+            while i < bytes.len() {
+                match bytes[i] {
+                    b'e' | b'E' | b'p' | b'P' if matches!(bytes.get(i + 1), Some(b'+' | b'-')) => {
+                        i += 2
+                    }
+                    b'.' | b'_' => i += 1,
+                    c if c.is_ascii_alphanumeric() => i += 1,
+                    _ => break,
+                }
+            }
+            PpTokenKind::Number
+        } else if c == b'"' || c == b'\'' {
+            i += 1;
+            while i < bytes.len() && bytes[i] != c {
+                i += if bytes[i] == b'\\' { 2 } else { 1 };
+            }
+            if i < bytes.len() {
+                i += 1;
+                if c == b'"' {
+                    PpTokenKind::String
+                } else {
+                    PpTokenKind::Character
+                }
+            } else {
+                i = start + 1;
+                PpTokenKind::Other
+            }
+        } else if let Some(punctuator) = PUNCTUATORS.iter().find(|p| text[i..].starts_with(*p)) {
+            i += punctuator.len();
+            PpTokenKind::Punctuator
+        } else {
+            i += text[i..].chars().next().unwrap().len_utf8();
+            PpTokenKind::Other
+        };
+
+        let span = span_of(start, i);
+        tokens.push(PpToken {
+            kind,
+            text: text[start..i].to_string(),
+            has_space,
+            column: span.column,
+            span,
+            is_from_source: true,
+            hide_set: vec![],
+        });
+        has_space = false;
+    }
+
+    tokens
+}
+
+fn tokenize_line(file: &Rc<SourceFile>, line: &Line) -> Vec<PpToken> {
+    tokenize_text(&line.text, |start, end| {
+        span_between(file, line.offsets[start], line.offsets[end - 1] + 1)
+    })
+}
+
+// Writes the tokens back as text, with a single space wherever there was whitespace
+fn spell(tokens: &[PpToken]) -> String {
+    let mut text = String::new();
+    for (i, token) in tokens.iter().enumerate() {
+        if i > 0 && token.has_space {
+            text.push(' ');
+        }
+        text.push_str(&token.text);
+    }
+    text
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn stringify(argument: &[PpToken], operator: &PpToken) -> PpToken {
+    let mut text = String::new();
+    for (i, token) in argument.iter().enumerate() {
+        if i > 0 && token.has_space {
+            text.push(' ');
+        }
+        match token.kind {
+            PpTokenKind::String | PpTokenKind::Character => {
+                text.push_str(&token.text.replace('\\', "\\\\").replace('"', "\\\""))
+            }
+            _ => text.push_str(&token.text),
+        }
+    }
+
+    PpToken {
+        kind: PpTokenKind::String,
+        text: format!("\"{}\"", text),
+        ..operator.clone()
+    }
+}
+
+fn paste(left: PpToken, right: &PpToken, operator: &PpToken) -> Result<PpToken, CompileError> {
+    if left.kind == PpTokenKind::Placemarker {
+        return Ok(right.clone());
+    }
+
+    let text = format!("{}{}", left.text, right.text);
+    let mut tokens = tokenize_text(&text, |_, _| operator.span.clone());
+    match tokens.pop() {
+        Some(token) if tokens.is_empty() && token.kind != PpTokenKind::Other => Ok(PpToken {
+            has_space: left.has_space,
+            ..token
+        }),
+        _ => Err(CompileError::Preprocess(
+            operator.span.clone(),
+            format!(
+                "pasting \"{}\" and \"{}\" does not give a valid preprocessing token",
+                left.text, right.text
+            ),
+        )),
+    }
+}
+
+// Collects the arguments of a function-like macro invocation, up to the closing
+// parenthesis. Returns None if the input ends before it.
+fn collect_arguments(
+    input: &mut VecDeque<PpToken>,
+    n_parameters: usize,
+    is_variadic: bool,
+) -> Option<(Vec<Vec<PpToken>>, PpToken)> {
+    let mut depth = 0;
+    // The first token is the opening parenthesis
+    let end = 1 + input.iter().skip(1).position(|token| {
+        if token.is_punctuator("(") {
+            depth += 1;
+        } else if token.is_punctuator(")") {
+            if depth == 0 {
+                return true;
+            }
+            depth -= 1;
+        }
+        false
+    })?;
+
+    let mut tokens: Vec<PpToken> = input.drain(..=end).collect();
+    let parenthesis = tokens.pop().unwrap();
+    Some((
+        split_arguments(&tokens[1..], n_parameters, is_variadic),
+        parenthesis,
+    ))
+}
+
+// Splits the tokens between the parentheses of an invocation at the commas that
+// are not nested in parentheses, the variadic argument takes all the rest
+fn split_arguments(
+    tokens: &[PpToken],
+    n_parameters: usize,
+    is_variadic: bool,
+) -> Vec<Vec<PpToken>> {
+    let mut arguments = vec![vec![]];
+    let mut depth = 0;
+
+    for token in tokens {
+        let is_variadic_argument = is_variadic && arguments.len() == n_parameters;
+        if token.is_punctuator(",") && depth == 0 && !is_variadic_argument {
+            arguments.push(vec![]);
+            continue;
+        }
+        if token.is_punctuator("(") {
+            depth += 1;
+        } else if token.is_punctuator(")") {
+            depth -= 1;
+        }
+        arguments.last_mut().unwrap().push(token.clone());
+    }
+
+    arguments
+}
+
+fn get_macro_name<'t>(
+    tokens: &'t [PpToken],
+    directive: &PpToken,
+) -> Result<&'t PpToken, CompileError> {
+    match tokens.first() {
+        Some(token) if token.kind == PpTokenKind::Identifier => Ok(token),
+        Some(token) => Err(CompileError::Preprocess(
+            token.span.clone(),
+            "macro names must be identifiers".to_string(),
+        )),
+        None => Err(CompileError::Preprocess(
+            directive.span.clone(),
+            format!("no macro name given in #{} directive", directive.text),
+        )),
+    }
+}
+
+// Parses the parameter list of a function-like macro, after the opening parenthesis.
+// Returns the parameters, whether the macro is variadic and the number of tokens
+// of the list.
+fn parse_macro_parameters(
+    tokens: &[PpToken],
+    name: &PpToken,
+) -> Result<(Vec<String>, bool, usize), CompileError> {
+    let mut parameters: Vec<String> = vec![];
+    let mut i = 0;
+
+    if tokens.first().is_some_and(|token| token.is_punctuator(")")) {
+        return Ok((parameters, false, 1));
+    }
+
+    loop {
+        let Some(token) = tokens.get(i) else {
+            return Err(CompileError::Preprocess(
+                name.span.clone(),
+                "missing ')' in macro parameter list".to_string(),
+            ));
+        };
+
+        let is_variadic = token.is_punctuator("...");
+        if is_variadic {
+            parameters.push("__VA_ARGS__".to_string());
+        } else if token.kind == PpTokenKind::Identifier {
+            if parameters.contains(&token.text) {
+                return Err(CompileError::Preprocess(
+                    token.span.clone(),
+                    format!("duplicate macro parameter \"{}\"", token.text),
+                ));
+            }
+            parameters.push(token.text.clone());
+        } else {
+            return Err(CompileError::Preprocess(
+                token.span.clone(),
+                format!("expected parameter name, found \"{}\"", token.text),
+            ));
+        }
+
+        match tokens.get(i + 1) {
+            Some(token) if token.is_punctuator(")") => return Ok((parameters, is_variadic, i + 2)),
+            Some(token) if token.is_punctuator(",") && !is_variadic => i += 2,
+            Some(token) => {
+                return Err(CompileError::Preprocess(
+                    token.span.clone(),
+                    format!("expected ',' or ')', found \"{}\"", token.text),
+                ))
+            }
+            None => {
+                return Err(CompileError::Preprocess(
+                    name.span.clone(),
+                    "missing ')' in macro parameter list".to_string(),
+                ))
+            }
+        }
+    }
+}
+
+impl<'a> Preprocessor<'a> {
+    fn preprocess_file(&mut self, file: &Rc<SourceFile>) -> Result<(), CompileError> {
+        self.sources.push(file.clone());
+        self.output.mark(1, &file.name);
+
+        let lines = split_lines(file)?;
+        let mut conditionals: Vec<Conditional> = vec![];
+        // Tokens of lines that end inside a macro invocation, joined with the next line
+        let mut pending: Vec<PpToken> = vec![];
+
+        for (i, line) in lines.iter().enumerate() {
+            let mut tokens = tokenize_line(file, line);
+            let is_directive = tokens.first().is_some_and(|token| token.is_punctuator("#"));
+
+            if is_directive {
+                self.write_line(file, &pending, false)?;
+                pending.clear();
+                self.directive(file, &tokens, &mut conditionals)?;
+                continue;
+            }
+            if !conditionals.iter().all(|conditional| conditional.is_active) {
+                continue;
+            }
+
+            if let (false, Some(token)) = (pending.is_empty(), tokens.first_mut()) {
+                token.has_space = true;
+            }
+            pending.extend(tokens);
+
+            let is_last = i + 1 == lines.len();
+            if self.write_line(file, &pending, !is_last)? {
+                pending.clear();
+            }
+        }
+
+        self.write_line(file, &pending, false)?;
+
+        match conditionals.last() {
+            Some(conditional) => Err(CompileError::Preprocess(
+                conditional.span.clone(),
+                "unterminated #if".to_string(),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    // Expands and writes the tokens of a line. Returns false, without writing, if the
+    // line ends inside a macro invocation and may continue on the next line.
+    fn write_line(
+        &mut self,
+        file: &Rc<SourceFile>,
+        tokens: &[PpToken],
+        may_continue: bool,
+    ) -> Result<bool, CompileError> {
+        let Some(first) = tokens.first() else {
+            return Ok(true);
+        };
+        self.line = first.span.line;
+
+        let Some(expanded) = self.expand(file, tokens.to_vec(), may_continue)? else {
+            return Ok(false);
+        };
+
+        self.output.move_to_line(self.line);
+        let mut is_after_source_token = false;
+        for token in expanded {
+            if token.is_from_source {
+                self.output.move_to_line(token.span.line);
+                let column = token.span.column - 1;
+                if self.output.column < column {
+                    let padding = " ".repeat(column - self.output.column);
+                    self.output.push_str(&padding);
+                } else if self.output.column > 0 && (token.has_space || !is_after_source_token) {
+                    self.output.push_str(" ");
+                }
+            } else if self.output.column > 0 {
+                self.output.push_str(" ");
+            }
+            let offset = self.output.text.len();
+            self.output.columns.push((offset, token.column));
+            self.output.push_str(&token.text);
+            is_after_source_token = token.is_from_source;
+        }
+
+        Ok(true)
+    }
+
+    fn directive(
+        &mut self,
+        file: &Rc<SourceFile>,
+        tokens: &[PpToken],
+        conditionals: &mut Vec<Conditional>,
+    ) -> Result<(), CompileError> {
+        let is_active = conditionals.iter().all(|conditional| conditional.is_active);
+        // The null directive, a line with only a #
+        let Some(directive) = tokens.get(1) else {
+            return Ok(());
+        };
+        let arguments = &tokens[2..];
+        self.line = directive.span.line;
+
+        match directive.text.as_str() {
+            "if" | "ifdef" | "ifndef" => {
+                let is_true = is_active && self.evaluate_condition(file, directive, arguments)?;
+                conditionals.push(Conditional {
+                    span: directive.span.clone(),
+                    is_active: is_true,
+                    was_taken: is_true || !is_active,
+                    has_else: false,
+                });
+            }
+            "elif" | "else" => {
+                let Some(conditional) = conditionals.last_mut() else {
+                    return Err(CompileError::Preprocess(
+                        directive.span.clone(),
+                        format!("#{} without #if", directive.text),
+                    ));
+                };
+                if conditional.has_else {
+                    return Err(CompileError::Preprocess(
+                        directive.span.clone(),
+                        format!("#{} after #else", directive.text),
+                    ));
+                }
+
+                let is_true = !conditional.was_taken
+                    && (directive.text == "else"
+                        || self.evaluate_condition(file, directive, arguments)?);
+                conditional.is_active = is_true;
+                conditional.was_taken |= is_true;
+                conditional.has_else = directive.text == "else";
+            }
+            "endif" => {
+                if conditionals.pop().is_none() {
+                    return Err(CompileError::Preprocess(
+                        directive.span.clone(),
+                        "#endif without #if".to_string(),
+                    ));
+                }
+            }
+            // The other directives are not checked in groups that are skipped
+            _ if !is_active => {}
+            "define" => self.define(directive, arguments)?,
+            "undef" => {
+                let name = get_macro_name(arguments, directive)?;
+                self.macros.remove(&name.text);
+            }
+            "include" => self.include(file, directive, arguments)?,
+            "error" => {
+                return Err(CompileError::Preprocess(
+                    directive.span.clone(),
+                    format!("#error {}", spell(arguments)),
+                ))
+            }
+            "pragma" => {} // no pragmas are supported, they are ignored
+            _ => {
+                return Err(CompileError::Preprocess(
+                    directive.span.clone(),
+                    format!("invalid preprocessing directive #{}", directive.text),
+                ))
+            }
+        }
+
+        Ok(())
+    }
+
+    fn evaluate_condition(
+        &self,
+        file: &Rc<SourceFile>,
+        directive: &PpToken,
+        arguments: &[PpToken],
+    ) -> Result<bool, CompileError> {
+        if directive.text == "if" || directive.text == "elif" {
+            return self.evaluate(file, directive, arguments);
+        }
+
+        let name = get_macro_name(arguments, directive)?;
+        Ok(self.is_defined(&name.text) == (directive.text == "ifdef"))
+    }
+
+    fn is_defined(&self, name: &str) -> bool {
+        self.macros.contains_key(name) || matches!(name, "__LINE__" | "__FILE__")
+    }
+
+    fn define(&mut self, directive: &PpToken, tokens: &[PpToken]) -> Result<(), CompileError> {
+        let name = get_macro_name(tokens, directive)?;
+        if name.text == "defined" {
+            return Err(CompileError::Preprocess(
+                name.span.clone(),
+                "\"defined\" cannot be used as a macro name".to_string(),
+            ));
+        }
+
+        // The parenthesis of a function-like macro comes right after its name
+        let (parameters, is_variadic, body_start) = match tokens.get(1) {
+            Some(token) if token.is_punctuator("(") && !token.has_space => {
+                let (parameters, is_variadic, length) = parse_macro_parameters(&tokens[2..], name)?;
+                (Some(parameters), is_variadic, 2 + length)
+            }
+            _ => (None, false, 1),
+        };
+
+        let mut body = tokens[body_start..].to_vec();
+        if let Some(token) = body.first_mut() {
+            token.has_space = false;
+        }
+
+        for token in [body.first(), body.last()].into_iter().flatten() {
+            if token.is_punctuator("##") {
+                return Err(CompileError::Preprocess(
+                    token.span.clone(),
+                    "'##' cannot appear at either end of a macro expansion".to_string(),
+                ));
+            }
+        }
+        if let Some(parameters) = &parameters {
+            for (i, token) in body.iter().enumerate() {
+                let is_before_parameter = body.get(i + 1).is_some_and(|next| {
+                    next.kind == PpTokenKind::Identifier && parameters.contains(&next.text)
+                });
+                if token.is_punctuator("#") && !is_before_parameter {
+                    return Err(CompileError::Preprocess(
+                        token.span.clone(),
+                        "'#' is not followed by a macro parameter".to_string(),
+                    ));
+                }
+            }
+        }
+
+        let definition = Macro {
+            parameters,
+            is_variadic,
+            body,
+        };
+        if let Some(previous) = self.macros.get(&name.text) {
+            if !previous.is_same_as(&definition) {
+                return Err(CompileError::Preprocess(
+                    name.span.clone(),
+                    format!("\"{}\" redefined", name.text),
+                ));
+            }
+        }
+        self.macros.insert(name.text.clone(), definition);
+
+        Ok(())
+    }
+
+    fn include(
+        &mut self,
+        file: &Rc<SourceFile>,
+        directive: &PpToken,
+        tokens: &[PpToken],
+    ) -> Result<(), CompileError> {
+        // Macros are expanded if the name is not written out
+        let tokens = match tokens.first() {
+            Some(token) if token.kind == PpTokenKind::String || token.is_punctuator("<") => {
+                tokens.to_vec()
+            }
+            _ => self
+                .expand(file, tokens.to_vec(), false)?
+                .unwrap_or_default(),
+        };
+
+        let (name, is_angled) = match tokens.first() {
+            Some(token) if token.kind == PpTokenKind::String => {
+                (token.text[1..token.text.len() - 1].to_string(), false)
+            }
+            Some(token) if token.is_punctuator("<") => {
+                let end = tokens
+                    .iter()
+                    .position(|token| token.is_punctuator(">"))
+                    .ok_or_else(|| {
+                        CompileError::Preprocess(
+                            token.span.clone(),
+                            "missing terminating > character".to_string(),
+                        )
+                    })?;
+                (spell(&tokens[1..end]), true)
+            }
+            _ => {
+                return Err(CompileError::Preprocess(
+                    directive.span.clone(),
+                    "#include expects \"FILENAME\" or <FILENAME>".to_string(),
+                ))
+            }
+        };
+
+        // A "file" is looked for next to the file that includes it first
+        let directory = Path::new(&file.name).parent().filter(|_| !is_angled);
+        let path = directory
+            .into_iter()
+            .chain(self.include_paths.iter().map(Path::new))
+            .map(|directory| directory.join(&name))
+            .find(|path| path.is_file())
+            .ok_or_else(|| {
+                CompileError::Preprocess(
+                    directive.span.clone(),
+                    format!("{}: No such file or directory", name),
+                )
+            })?;
+
+        if self.depth == MAX_INCLUDE_DEPTH {
+            return Err(CompileError::Preprocess(
+                directive.span.clone(),
+                format!(
+                    "#include nested depth {} exceeds maximum of {}",
+                    self.depth, MAX_INCLUDE_DEPTH
+                ),
+            ));
+        }
+
+        let path = path.to_string_lossy().to_string();
+        let content =
+            fs::read_to_string(&path).map_err(|error| CompileError::Io(path.clone(), error))?;
+
+        self.depth += 1;
+        self.preprocess_file(&Rc::new(SourceFile::new(path, content)))?;
+        self.depth -= 1;
+
+        self.output.mark(directive.span.line + 1, &file.name);
+        Ok(())
+    }
+
+    // Expands the macros in the tokens, a token is not expanded again by the
+    // macros in its hide set. Returns None if the tokens may continue and end in
+    // a function-like macro invocation, or with the name of a function-like macro.
+    fn expand(
+        &self,
+        file: &Rc<SourceFile>,
+        tokens: Vec<PpToken>,
+        may_continue: bool,
+    ) -> Result<Option<Vec<PpToken>>, CompileError> {
+        let mut input: VecDeque<PpToken> = tokens.into();
+        let mut output = vec![];
+
+        while let Some(token) = input.pop_front() {
+            let definition = match self.macros.get(&token.text) {
+                Some(definition)
+                    if token.kind == PpTokenKind::Identifier
+                        && !token.hide_set.contains(&token.text) =>
+                {
+                    definition
+                }
+                _ => {
+                    output.push(self.expand_builtin(file, token));
+                    continue;
+                }
+            };
+
+            let mut hide_set = token.hide_set.clone();
+            let arguments = match &definition.parameters {
+                None => vec![],
+                Some(parameters) => {
+                    match input.front() {
+                        Some(next) if next.is_punctuator("(") => {}
+                        None if may_continue => return Ok(None),
+                        _ => {
+                            output.push(token);
+                            continue;
+                        }
+                    }
+
+                    let Some((mut arguments, parenthesis)) =
+                        collect_arguments(&mut input, parameters.len(), definition.is_variadic)
+                    else {
+                        if may_continue {
+                            return Ok(None);
+                        }
+                        return Err(CompileError::Preprocess(
+                            token.span.clone(),
+                            format!(
+                                "unterminated argument list invoking macro \"{}\"",
+                                token.text
+                            ),
+                        ));
+                    };
+                    check_arguments(&token, parameters, definition.is_variadic, &mut arguments)?;
+
+                    hide_set.retain(|name| parenthesis.hide_set.contains(name));
+                    arguments
+                }
+            };
+            hide_set.push(token.text.clone());
+
+            let mut body =
+                self.substitute(file, definition, &arguments, &hide_set, token.column)?;
+            if let Some(first) = body.first_mut() {
+                first.has_space = token.has_space;
+            }
+            for token in body.into_iter().rev() {
+                input.push_front(token);
+            }
+        }
+
+        Ok(Some(output))
+    }
+
+    fn expand_builtin(&self, file: &Rc<SourceFile>, token: PpToken) -> PpToken {
+        if token.kind != PpTokenKind::Identifier {
+            return token;
+        }
+
+        let (kind, text) = match token.text.as_str() {
+            // Written in the file, the line is the line of the token itself
+            "__LINE__" if token.is_from_source => {
+                (PpTokenKind::Number, token.span.line.to_string())
+            }
+            "__LINE__" => (PpTokenKind::Number, self.line.to_string()),
+            "__FILE__" => (PpTokenKind::String, quote(&file.name)),
+            _ => return token,
+        };
+
+        PpToken {
+            kind,
+            text,
+            is_from_source: false,
+            ..token
+        }
+    }
+
+    // Replaces the parameters in the body of a macro with their arguments, and applies
+    // the # and ## operators. The arguments are expanded first, unless they are an
+    // operand of # or ##. The tokens of the body are placed at the column of the
+    // invocation, the arguments keep their own.
+    fn substitute(
+        &self,
+        file: &Rc<SourceFile>,
+        definition: &Macro,
+        arguments: &[Vec<PpToken>],
+        hide_set: &[String],
+        column: usize,
+    ) -> Result<Vec<PpToken>, CompileError> {
+        let parameters = definition.parameters.as_deref().unwrap_or_default();
+        let get_argument = |token: &PpToken| {
+            parameters
+                .iter()
+                .position(|parameter| {
+                    token.kind == PpTokenKind::Identifier && *parameter == token.text
+                })
+                .map(|i| &arguments[i])
+        };
+
+        let body = &definition.body;
+        let mut result: Vec<PpToken> = vec![];
+        let mut i = 0;
+        while i < body.len() {
+            let token = &body[i];
+            let is_pasted = body.get(i + 1).is_some_and(|next| next.is_punctuator("##"));
+
+            if token.is_punctuator("#") && definition.parameters.is_some() {
+                result.push(PpToken {
+                    column,
+                    ..stringify(get_argument(&body[i + 1]).unwrap(), token)
+                });
+                i += 2;
+            } else if token.is_punctuator("##") {
+                let left = result.pop().unwrap();
+                let right = match get_argument(&body[i + 1]) {
+                    Some(argument) => argument.clone(),
+                    None => vec![body[i + 1].clone()],
+                };
+                match right.split_first() {
+                    Some((first, rest)) => {
+                        result.push(PpToken {
+                            column,
+                            ..paste(left, first, token)?
+                        });
+                        result.extend(rest.iter().cloned());
+                    }
+                    None => result.push(left),
+                }
+                i += 2;
+            } else if let Some(argument) = get_argument(token) {
+                let mut tokens = match (is_pasted, argument.is_empty()) {
+                    (true, true) => vec![PpToken {
+                        kind: PpTokenKind::Placemarker,
+                        text: String::new(),
+                        ..token.clone()
+                    }],
+                    (true, false) => argument.clone(),
+                    (false, _) => self
+                        .expand(file, argument.clone(), false)?
+                        .unwrap_or_default(),
+                };
+                if let Some(first) = tokens.first_mut() {
+                    first.has_space = token.has_space;
+                }
+                result.extend(tokens);
+                i += 1;
+            } else {
+                result.push(PpToken {
+                    column,
+                    ..token.clone()
+                });
+                i += 1;
+            }
+        }
+
+        result.retain(|token| token.kind != PpTokenKind::Placemarker);
+        for token in &mut result {
+            token.is_from_source = false;
+            for name in hide_set {
+                if !token.hide_set.contains(name) {
+                    token.hide_set.push(name.clone());
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    // Evaluates the expression of an #if or #elif directive
+    fn evaluate(
+        &self,
+        file: &Rc<SourceFile>,
+        directive: &PpToken,
+        tokens: &[PpToken],
+    ) -> Result<bool, CompileError> {
+        // defined is applied before the macros are expanded
+        let mut replaced = vec![];
+        let mut i = 0;
+        while i < tokens.len() {
+            let token = &tokens[i];
+            if token.kind != PpTokenKind::Identifier || token.text != "defined" {
+                replaced.push(token.clone());
+                i += 1;
+                continue;
+            }
+
+            let is_parenthesized = tokens
+                .get(i + 1)
+                .is_some_and(|next| next.is_punctuator("("));
+            let name = tokens
+                .get(i + 1 + usize::from(is_parenthesized))
+                .filter(|name| name.kind == PpTokenKind::Identifier);
+            let is_closed = !is_parenthesized
+                || tokens
+                    .get(i + 3)
+                    .is_some_and(|next| next.is_punctuator(")"));
+
+            let (Some(name), true) = (name, is_closed) else {
+                return Err(CompileError::Preprocess(
+                    token.span.clone(),
+                    "operator \"defined\" requires an identifier".to_string(),
+                ));
+            };
+            replaced.push(PpToken {
+                kind: PpTokenKind::Number,
+                text: u8::from(self.is_defined(&name.text)).to_string(),
+                ..token.clone()
+            });
+            i += if is_parenthesized { 4 } else { 2 };
+        }
+
+        let tokens = self.expand(file, replaced, false)?.unwrap_or_default();
+        if tokens.is_empty() {
+            return Err(CompileError::Preprocess(
+                directive.span.clone(),
+                format!("#{} with no expression", directive.text),
+            ));
+        }
+
+        let mut expression = Expression {
+            tokens: &tokens,
+            pos: 0,
+        };
+        let value = expression.parse_conditional(true)?;
+        if let Some(token) = tokens.get(expression.pos) {
+            return Err(CompileError::Preprocess(
+                token.span.clone(),
+                format!("missing binary operator before token \"{}\"", token.text),
+            ));
+        }
+
+        Ok(value.value != 0)
+    }
+}
+
+fn check_arguments(
+    name: &PpToken,
+    parameters: &[String],
+    is_variadic: bool,
+    arguments: &mut Vec<Vec<PpToken>>,
+) -> Result<(), CompileError> {
+    // The parentheses of an invocation without arguments hold a single empty argument
+    if parameters.is_empty() && arguments.len() == 1 && arguments[0].is_empty() {
+        arguments.clear();
+    }
+    // The variadic argument may be left out entirely
+    if is_variadic && arguments.len() + 1 == parameters.len() {
+        arguments.push(vec![]);
+    }
+
+    match arguments.len().cmp(&parameters.len()) {
+        Ordering::Less => Err(CompileError::Preprocess(
+            name.span.clone(),
+            format!(
+                "macro \"{}\" requires {} arguments, but only {} given",
+                name.text,
+                parameters.len(),
+                arguments.len()
+            ),
+        )),
+        Ordering::Greater => Err(CompileError::Preprocess(
+            name.span.clone(),
+            format!(
+                "macro \"{}\" passed {} arguments, but takes just {}",
+                name.text,
+                arguments.len(),
+                parameters.len()
+            ),
+        )),
+        Ordering::Equal => Ok(()),
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Value {
+    value: i64,
+    is_unsigned: bool,
+}
+
+// The expression of an #if directive, evaluated as it is parsed. The operands that
+// are not evaluated, such as the right of && with a false left, can't divide by zero.
+struct Expression<'t> {
+    tokens: &'t [PpToken],
+    pos: usize,
+}
+
+fn get_binary_precedence(token: &PpToken) -> Option<u8> {
+    if token.kind != PpTokenKind::Punctuator {
+        return None;
+    }
+
+    match token.text.as_str() {
+        "*" | "/" | "%" => Some(10),
+        "+" | "-" => Some(9),
+        "<<" | ">>" => Some(8),
+        "<" | ">" | "<=" | ">=" => Some(7),
+        "==" | "!=" => Some(6),
+        "&" => Some(5),
+        "^" => Some(4),
+        "|" => Some(3),
+        "&&" => Some(2),
+        "||" => Some(1),
+        _ => None,
+    }
+}
+
+fn apply_binary_operator(
+    operator: &PpToken,
+    left: Value,
+    right: Value,
+    is_evaluated: bool,
+) -> Result<Value, CompileError> {
+    let is_unsigned = left.is_unsigned || right.is_unsigned;
+    let (l, r) = (left.value, right.value);
+    let ordering = if is_unsigned {
+        (l as u64).cmp(&(r as u64))
+    } else {
+        l.cmp(&r)
+    };
+    let shift = u32::try_from(r).ok().filter(|&shift| shift < i64::BITS);
+
+    let value = match operator.text.as_str() {
+        "/" | "%" if r == 0 => {
+            if is_evaluated {
+                return Err(CompileError::Preprocess(
+                    operator.span.clone(),
+                    "division by zero in #if".to_string(),
+                ));
+            }
+            0
+        }
+        "*" => l.wrapping_mul(r),
+        "/" if is_unsigned => ((l as u64) / (r as u64)) as i64,
+        "/" => l.wrapping_div(r),
+        "%" if is_unsigned => ((l as u64) % (r as u64)) as i64,
+        "%" => l.wrapping_rem(r),
+        "+" => l.wrapping_add(r),
+        "-" => l.wrapping_sub(r),
+        "<<" => shift.map_or(0, |shift| l << shift),
+        ">>" if left.is_unsigned => shift.map_or(0, |shift| ((l as u64) >> shift) as i64),
+        ">>" => shift.map_or(if l < 0 { -1 } else { 0 }, |shift| l >> shift),
+        "<" => i64::from(ordering.is_lt()),
+        ">" => i64::from(ordering.is_gt()),
+        "<=" => i64::from(ordering.is_le()),
+        ">=" => i64::from(ordering.is_ge()),
+        "==" => i64::from(l == r),
+        "!=" => i64::from(l != r),
+        "&" => l & r,
+        "^" => l ^ r,
+        "|" => l | r,
+        "&&" => i64::from(l != 0 && r != 0),
+        _ => i64::from(l != 0 || r != 0),
+    };
+
+    let is_unsigned = match operator.text.as_str() {
+        "<<" | ">>" => left.is_unsigned,
+        "<" | ">" | "<=" | ">=" | "==" | "!=" | "&&" | "||" => false,
+        _ => is_unsigned,
+    };
+    Ok(Value { value, is_unsigned })
+}
+
+impl<'t> Expression<'t> {
+    fn parse_conditional(&mut self, is_evaluated: bool) -> Result<Value, CompileError> {
+        let condition = self.parse_binary(1, is_evaluated)?;
+
+        let operator = match self.tokens.get(self.pos) {
+            Some(token) if token.is_punctuator("?") => token,
+            _ => return Ok(condition),
+        };
+        self.pos += 1;
+
+        let is_true = condition.value != 0;
+        let left = self.parse_conditional(is_evaluated && is_true)?;
+        match self.tokens.get(self.pos) {
+            Some(token) if token.is_punctuator(":") => self.pos += 1,
+            _ => {
+                return Err(CompileError::Preprocess(
+                    operator.span.clone(),
+                    "'?' without following ':'".to_string(),
+                ))
+            }
+        }
+        let right = self.parse_conditional(is_evaluated && !is_true)?;
+
+        Ok(Value {
+            value: if is_true { left.value } else { right.value },
+            is_unsigned: left.is_unsigned || right.is_unsigned,
+        })
+    }
+
+    fn parse_binary(
+        &mut self,
+        min_precedence: u8,
+        is_evaluated: bool,
+    ) -> Result<Value, CompileError> {
+        let mut left = self.parse_unary(is_evaluated)?;
+
+        while let Some(operator) = self.tokens.get(self.pos) {
+            let Some(precedence) =
+                get_binary_precedence(operator).filter(|&precedence| precedence >= min_precedence)
+            else {
+                break;
+            };
+            self.pos += 1;
+
+            let is_right_evaluated = match operator.text.as_str() {
+                "&&" => is_evaluated && left.value != 0,
+                "||" => is_evaluated && left.value == 0,
+                _ => is_evaluated,
+            };
+            let right = self.parse_binary(precedence + 1, is_right_evaluated)?;
+            left = apply_binary_operator(operator, left, right, is_right_evaluated)?;
+        }
+
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self, is_evaluated: bool) -> Result<Value, CompileError> {
+        let Some(token) = self.tokens.get(self.pos) else {
+            let operator = &self.tokens[self.pos - 1];
+            return Err(CompileError::Preprocess(
+                operator.span.clone(),
+                format!("operator '{}' has no right operand", operator.text),
+            ));
+        };
+        self.pos += 1;
+
+        match (token.kind, token.text.as_str()) {
+            (PpTokenKind::Number, _) => match lex_integer(&token.text) {
+                Ok(TokenKind::Integer(value, ctype)) => Ok(Value {
+                    value: value as i64,
+                    is_unsigned: !ctype.is_signed() || value > i64::MAX as u64,
+                }),
+                Ok(_) => unreachable!(),
+                Err(message) => Err(CompileError::Preprocess(token.span.clone(), message)),
+            },
+            (PpTokenKind::Character, _) => {
+                let mut it = token.text[1..].char_indices().peekable();
+                match lex_character_constant(&mut it) {
+                    // char is signed
+                    Ok(value) => Ok(Value {
+                        value: i64::from(value as i8),
+                        is_unsigned: false,
+                    }),
+                    Err(message) => Err(CompileError::Preprocess(token.span.clone(), message)),
+                }
+            }
+            // Identifiers that are left after expanding the macros
+            (PpTokenKind::Identifier, _) => Ok(Value {
+                value: 0,
+                is_unsigned: false,
+            }),
+            (PpTokenKind::Punctuator, "(") => {
+                let value = self.parse_conditional(is_evaluated)?;
+                match self.tokens.get(self.pos) {
+                    Some(next) if next.is_punctuator(")") => {
+                        self.pos += 1;
+                        Ok(value)
+                    }
+                    _ => Err(CompileError::Preprocess(
+                        token.span.clone(),
+                        "missing ')' in expression".to_string(),
+                    )),
+                }
+            }
+            (PpTokenKind::Punctuator, operator @ ("+" | "-" | "~" | "!")) => {
+                let operand = self.parse_unary(is_evaluated)?;
+                let value = match operator {
+                    "+" => operand.value,
+                    "-" => operand.value.wrapping_neg(),
+                    "~" => !operand.value,
+                    _ => i64::from(operand.value == 0),
+                };
+                Ok(Value {
+                    value,
+                    is_unsigned: operand.is_unsigned && operator != "!",
+                })
+            }
+            _ => Err(CompileError::Preprocess(
+                token.span.clone(),
+                format!(
+                    "token \"{}\" is not valid in preprocessor expressions",
+                    token.text
+                ),
+            )),
+        }
+    }
+}
+
+// Runs the preprocessor over the file. The result has line markers that give the
+// file and line every line of it comes from.
+pub fn preprocess(
+    file: &Rc<SourceFile>,
+    include_paths: &[String],
+) -> Result<Rc<SourceFile>, CompileError> {
+    let mut preprocessor = Preprocessor {
+        include_paths,
+        macros: HashMap::new(),
+        sources: vec![],
+        output: Output {
+            text: String::new(),
+            name: file.name.clone(),
+            line: 1,
+            column: 0,
+            marker_start: None,
+            columns: vec![],
+        },
+        line: 1,
+        depth: 0,
+    };
+    preprocessor.preprocess_file(file)?;
+
+    // The output ends without a newline if the file does, so that the end of file
+    // is on a line of the file
+    Ok(Rc::new(SourceFile::preprocessed(
+        file.name.clone(),
+        preprocessor.output.text,
+        preprocessor.output.columns,
+        &preprocessor.sources,
+    )))
+}
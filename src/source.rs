@@ -0,0 +1,187 @@
+use std::fmt;
+use std::rc::Rc;
+
+#[derive(Debug)]
+pub struct SourceFile {
+    pub name: String,
+    pub content: String,
+    line_starts: Vec<usize>, // byte offset at which every line starts
+    line_origins: Vec<Option<(Rc<SourceFile>, usize)>>, // file and line every line was preprocessed from
+    column_origins: Vec<(usize, usize)>, // byte offset of every preprocessed token, and its column in the source
+}
+
+impl SourceFile {
+    pub fn new(name: String, content: String) -> SourceFile {
+        let mut line_starts = vec![0];
+        for (i, c) in content.char_indices() {
+            if c == '\n' {
+                line_starts.push(i + 1);
+            }
+        }
+
+        SourceFile {
+            name,
+            content,
+            line_starts,
+            line_origins: vec![],
+            column_origins: vec![],
+        }
+    }
+
+    // The line markers in preprocessed content give the file and line of the line
+    // after them, the lines that follow count on from there. Spans in the content
+    // point at the lines of the source files instead, and at the columns the tokens
+    // were written at, as macro expansions move the tokens after them.
+    pub fn preprocessed(
+        name: String,
+        content: String,
+        column_origins: Vec<(usize, usize)>,
+        sources: &[Rc<SourceFile>],
+    ) -> SourceFile {
+        let mut file = SourceFile::new(name, content);
+        file.column_origins = column_origins;
+        let mut origin: Option<(Rc<SourceFile>, usize)> = None;
+
+        file.line_origins = (1..=file.line_starts.len())
+            .map(|line| {
+                if let Some((line, name)) = parse_line_marker(file.get_line(line)) {
+                    let source = sources.iter().find(|source| source.name == name);
+                    origin = source.map(|source| (source.clone(), line));
+                    return None;
+                }
+
+                let line_origin = origin.clone();
+                if let Some((_, line)) = &mut origin {
+                    *line += 1;
+                }
+                line_origin
+            })
+            .collect();
+
+        file
+    }
+
+    pub fn get_line(&self, line: usize) -> &str {
+        let start = self.line_starts[line - 1];
+        let end = match self.line_starts.get(line) {
+            Some(next_start) => next_start - 1,
+            None => self.content.len(),
+        };
+
+        self.content[start..end].trim_end_matches('\r')
+    }
+}
+
+// Creates the span of the bytes from start up to end in the file
+pub fn span_between(file: &Rc<SourceFile>, start: usize, end: usize) -> Span {
+    let line = file.line_starts.partition_point(|&x| x <= start);
+    let line_start = file.line_starts[line - 1];
+    let (source, source_line) = match file.line_origins.get(line - 1) {
+        Some(Some((source, source_line))) => (source, *source_line),
+        _ => (file, line),
+    };
+
+    // Counted from the closest token before the start on the same line
+    let (anchor, anchor_column) = match file
+        .column_origins
+        .partition_point(|&(offset, _)| offset <= start)
+    {
+        0 => (line_start, 1),
+        i => match file.column_origins[i - 1] {
+            (offset, column) if offset >= line_start => (offset, column),
+            _ => (line_start, 1),
+        },
+    };
+
+    Span {
+        file: source.clone(),
+        line: source_line,
+        column: anchor_column + file.content[anchor..start].chars().count(),
+        length: file.content[start..end].chars().count(),
+    }
+}
+
+// A gcc style line marker, '# <line> "<file>"', with backslashes and quotes in the
+// name of the file escaped
+pub fn format_line_marker(line: usize, name: &str) -> String {
+    format!(
+        "# {} \"{}\"",
+        line,
+        name.replace('\\', "\\\\").replace('"', "\\\"")
+    )
+}
+
+pub fn parse_line_marker(line: &str) -> Option<(usize, String)> {
+    let (number, name) = line.strip_prefix("# ")?.split_once(' ')?;
+    let name = name.strip_prefix('"')?.strip_suffix('"')?;
+
+    let mut unescaped = String::new();
+    let mut chars = name.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.push(chars.next()?),
+            c => unescaped.push(c),
+        }
+    }
+
+    Some((number.parse().ok()?, unescaped))
+}
+
+#[derive(Clone)]
+pub struct Span {
+    pub file: Rc<SourceFile>,
+    pub line: usize,   // starts at 1
+    pub column: usize, // starts at 1, counted in characters
+    pub length: usize, // counted in characters
+}
+
+impl Span {
+    pub fn render_error(&self, message: &str) -> String {
+        self.render_diagnostic("error", message)
+    }
+
+    pub fn render_warning(&self, message: &str) -> String {
+        self.render_diagnostic("warning", message)
+    }
+
+    // Renders a gcc style diagnostic, with the source line and a caret underneath the span
+    fn render_diagnostic(&self, severity: &str, message: &str) -> String {
+        let source_line = self.file.get_line(self.line);
+        let indentation: String = source_line
+            .chars()
+            .take(self.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let underline = format!("^{}", "~".repeat(self.length.saturating_sub(1)));
+
+        format!(
+            "{}:{}:{}: {}: {}\n{:>5} | {}\n      | {}{}",
+            self.file.name,
+            self.line,
+            self.column,
+            severity,
+            message,
+            self.line,
+            source_line,
+            indentation,
+            underline
+        )
+    }
+}
+
+impl fmt::Debug for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file.name, self.line, self.column)
+    }
+}
+
+impl PartialEq for Span {
+    fn eq(&self, other: &Span) -> bool {
+        Rc::ptr_eq(&self.file, &other.file)
+            && self.line == other.line
+            && self.column == other.column
+            && self.length == other.length
+    }
+}
+
+impl Eq for Span {}
@@ -1,79 +1,2143 @@
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 
 use crate::constants::QUADWORD_LENGTH;
-use crate::parser::{NodeType, ParseNode};
+use crate::error::{CompileError, CompileWarning};
+use crate::parser::{BinaryOp, NodeType, ParseNode, StorageClass, UnaryOp};
+use crate::source::Span;
+use crate::types::{get_common_type, CType, StructKind, StructType};
 
 #[derive(Clone, Debug)]
 struct Variable {
     name: String,
     offset: usize,
+    ctype: CType,
+    usage: Option<usize>, // index of the usage in the symbol table, if it is tracked
+    label: Option<String>, // label of a variable with static storage duration
+    value: Option<i64>,   // value of an enumeration constant, which is not a variable
 }
 
+// A variable with static storage duration lives in the data of the binary for the
+// whole run of the program. It is defined by a declaration without extern or with
+// an initializer, and global when it has external linkage.
 #[derive(Clone, Debug)]
-struct SymbolTable {
+struct StaticObject {
+    label: String,
+    ctype: CType,
+    span: Span,
+    is_global: bool,
+    is_defined: bool,
+    initializer: Option<ParseNode>, // the flattened constant initializer
+}
+
+// Whether a variable is ever given a value, used to warn about reads of variables
+// that never are. Compound assignments read the old value, so they do not count.
+#[derive(Clone, Debug)]
+struct Usage {
+    name: String,
+    is_assigned: bool,
+    first_read: Option<Span>,
+}
+
+// Struct tags are scoped like variables, a struct is incomplete in its own scope
+// until the end of its definition
+#[derive(Clone, Debug)]
+struct Scope {
     variables: HashMap<String, Variable>,
+    structs: HashMap<String, CType>,
+    stack_start: usize, // the stack below this size belongs to the enclosing scopes
 }
 
-impl SymbolTable {
-    fn new() -> SymbolTable {
-        SymbolTable {
+impl Scope {
+    fn new(stack_start: usize) -> Scope {
+        Scope {
             variables: HashMap::new(),
+            structs: HashMap::new(),
+            stack_start,
         }
     }
+}
 
-    fn add(&mut self, name: &String, offset: usize) {
-        self.variables.insert(
-            name.to_owned(),
-            Variable {
-                name: name.to_owned(),
-                offset,
-            },
-        );
+// The arguments of a variadic function after its parameters are only promoted
+#[derive(Eq, PartialEq, Clone, Debug)]
+struct Function {
+    return_type: CType,
+    parameters: Vec<CType>,
+    is_variadic: bool,
+}
+
+// Stack space is handed out per scope and released again when the scope ends,
+// so sibling scopes reuse the same space.
+#[derive(Clone, Debug)]
+struct SymbolTable<'a> {
+    functions: &'a HashMap<String, Function>,
+    function_name: String,
+    return_type: CType,
+    scopes: Vec<Scope>,
+    stack_in_use: usize,
+    max_stack: usize,
+    usages: Vec<Usage>,
+    statics: Vec<StaticObject>,
+}
+
+impl SymbolTable<'_> {
+    // The scopes of a function are nested in the file scope
+    fn new(
+        functions: &HashMap<String, Function>,
+        file_scope: Scope,
+        function_name: String,
+        return_type: CType,
+    ) -> SymbolTable<'_> {
+        SymbolTable {
+            functions,
+            function_name,
+            return_type,
+            scopes: vec![file_scope],
+            stack_in_use: 0,
+            max_stack: 0,
+            usages: vec![],
+            statics: vec![],
+        }
+    }
+
+    fn is_file_scope(&self) -> bool {
+        self.scopes.len() == 1
+    }
+
+    fn enter_scope(&mut self) {
+        self.scopes.push(Scope::new(self.stack_in_use));
+    }
+
+    fn leave_scope(&mut self) {
+        let scope = self
+            .scopes
+            .pop()
+            .expect("Left more scopes than were entered");
+        self.stack_in_use = scope.stack_start;
+    }
+
+    // Variables are aligned to their alignment, and their offset points at their lowest byte
+    fn add(
+        &mut self,
+        name: &String,
+        ctype: &CType,
+        span: &Span,
+        is_assigned: bool,
+    ) -> Result<Variable, CompileError> {
+        let scope = self
+            .scopes
+            .last_mut()
+            .expect("Declaration outside of a scope");
+
+        if scope.variables.contains_key(name) {
+            return Err(CompileError::Semantic(
+                span.clone(),
+                format!("redefinition of '{}'", name),
+            ));
+        }
+
+        self.stack_in_use = (self.stack_in_use + ctype.size()).next_multiple_of(ctype.alignment());
+        self.max_stack = self.max_stack.max(self.stack_in_use);
+
+        let variable = Variable {
+            name: name.to_owned(),
+            offset: self.stack_in_use,
+            ctype: ctype.clone(),
+            usage: Some(self.usages.len()),
+            label: None,
+            value: None,
+        };
+        scope.variables.insert(name.to_owned(), variable.clone());
+        self.usages.push(Usage {
+            name: name.to_owned(),
+            is_assigned,
+            first_read: None,
+        });
+
+        Ok(variable)
+    }
+
+    // All declarations of a variable at file scope, and the extern declarations in
+    // blocks, refer to the same object, which is labeled with its name. A static
+    // variable in a block is an object of its own.
+    fn add_static(
+        &mut self,
+        name: &String,
+        ctype: &CType,
+        span: &Span,
+        storage_class: &StorageClass,
+        is_definition: bool,
+    ) -> Result<Variable, CompileError> {
+        let label = match storage_class {
+            StorageClass::Static if !self.is_file_scope() => {
+                format!("{}.{}.{}", self.function_name, name, self.statics.len())
+            }
+            _ => name.to_owned(),
+        };
+
+        // Only variables may be declared more than once at file scope
+        let scope = self.scopes.last().expect("Declaration outside of a scope");
+        if scope
+            .variables
+            .get(name)
+            .is_some_and(|previous| !self.is_file_scope() || previous.value.is_some())
+        {
+            return Err(CompileError::Semantic(
+                span.clone(),
+                format!("redefinition of '{}'", name),
+            ));
+        }
+
+        // An extern declaration in a block has to agree with the one at file scope
+        let mut ctype = ctype.clone();
+        let is_file_scope = self.is_file_scope();
+        if let Some(previous) = self.scopes[0].variables.get(name) {
+            if is_file_scope || *storage_class == StorageClass::Extern {
+                ctype = get_composite_type(&previous.ctype, &ctype).ok_or_else(|| {
+                    CompileError::Semantic(
+                        span.clone(),
+                        format!("conflicting types for '{}'", name),
+                    )
+                })?;
+            }
+        }
+
+        // An extern declaration keeps the linkage of the previous declaration
+        let previous = self
+            .statics
+            .iter_mut()
+            .find(|object| object.label == label)
+            .filter(|_| is_file_scope);
+        match (storage_class, previous) {
+            (StorageClass::Static, Some(previous)) if previous.is_global => {
+                return Err(CompileError::Semantic(
+                    span.clone(),
+                    format!(
+                        "static declaration of '{}' follows non-static declaration",
+                        name
+                    ),
+                ))
+            }
+            (StorageClass::Auto, Some(previous)) if !previous.is_global => {
+                return Err(CompileError::Semantic(
+                    span.clone(),
+                    format!(
+                        "non-static declaration of '{}' follows static declaration",
+                        name
+                    ),
+                ))
+            }
+            (_, Some(previous)) => {
+                previous.ctype = ctype.clone();
+                previous.is_defined |= is_definition;
+            }
+            (_, None) => self.statics.push(StaticObject {
+                label: label.clone(),
+                ctype: ctype.clone(),
+                span: span.clone(),
+                is_global: *storage_class != StorageClass::Static,
+                is_defined: is_definition,
+                initializer: None,
+            }),
+        }
+
+        // Static variables are always initialized, with 0 if nothing else, so their
+        // usage is not tracked
+        let variable = Variable {
+            name: name.to_owned(),
+            offset: 0,
+            ctype,
+            usage: None,
+            label: Some(label),
+            value: None,
+        };
+        self.scopes
+            .last_mut()
+            .expect("Declaration outside of a scope")
+            .variables
+            .insert(name.to_owned(), variable.clone());
+
+        Ok(variable)
+    }
+
+    // An enumeration constant is an ordinary identifier like a variable, but it
+    // designates its value rather than an object
+    fn add_constant(&mut self, name: &String, value: i64, span: &Span) -> Result<(), CompileError> {
+        let scope = self
+            .scopes
+            .last_mut()
+            .expect("Declaration outside of a scope");
+        if scope.variables.contains_key(name) {
+            return Err(CompileError::Semantic(
+                span.clone(),
+                format!("redefinition of '{}'", name),
+            ));
+        }
+
+        let constant = Variable {
+            name: name.to_owned(),
+            offset: 0,
+            ctype: CType::Int,
+            usage: None,
+            label: None,
+            value: Some(value),
+        };
+        scope.variables.insert(name.to_owned(), constant);
+
+        Ok(())
+    }
+
+    // A static variable is initialized only once, by the data of the binary
+    fn set_static_initializer(
+        &mut self,
+        variable: &Variable,
+        initializer: ParseNode,
+    ) -> Result<(), CompileError> {
+        let object = self
+            .statics
+            .iter_mut()
+            .find(|object| Some(&object.label) == variable.label.as_ref())
+            .expect("Static variables are added before they are initialized");
+
+        if object.initializer.is_some() {
+            return Err(CompileError::Semantic(
+                initializer.span.clone(),
+                format!("redefinition of '{}'", variable.name),
+            ));
+        }
+        object.initializer = Some(initializer);
+
+        Ok(())
+    }
+
+    // The innermost declaration shadows the ones in the enclosing scopes
+    fn get_var(&self, name: &String) -> Option<Variable> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.variables.get(name))
+            .cloned()
+    }
+
+    fn get_struct(&self, tag: &String) -> Option<CType> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.structs.get(tag))
+            .cloned()
+    }
+
+    // Replaces the struct tags in the type with their definitions, and evaluates
+    // the array lengths. A pointer may point to a struct that is not defined yet,
+    // which then stays incomplete.
+    fn resolve_type(&mut self, ctype: &CType) -> Result<CType, CompileError> {
+        Ok(match ctype {
+            CType::Struct(struct_type) if struct_type.members.is_none() => self
+                .get_struct(&struct_type.tag)
+                .unwrap_or_else(|| ctype.clone()),
+            CType::Pointer(pointee) => CType::Pointer(Box::new(self.resolve_type(pointee)?)),
+            CType::Array(element, length) => {
+                CType::Array(Box::new(self.resolve_type(element)?), *length)
+            }
+            CType::UnevaluatedArray(element, length) => CType::Array(
+                Box::new(self.resolve_type(element)?),
+                evaluate_array_length(length, self)?,
+            ),
+            CType::Const(ctype) => self.resolve_type(ctype)?.to_const(),
+            ctype => ctype.clone(),
+        })
+    }
+
+    // The members are laid out once all of them are declared, the nested
+    // definitions are declared in the same scope as the struct itself
+    fn define_struct(&mut self, node: &mut ParseNode) -> Result<(), CompileError> {
+        let NodeType::StructDecl(CType::Struct(struct_type)) = &node.node_type else {
+            panic!("{:?} is not a struct definition", node)
+        };
+        let struct_type = struct_type.clone();
+        let tag = struct_type.tag.clone();
+
+        let scope = self
+            .scopes
+            .last_mut()
+            .expect("Declaration outside of a scope");
+        if scope.structs.get(&tag).is_some_and(CType::is_complete) {
+            return Err(CompileError::Semantic(
+                node.span.clone(),
+                format!("redefinition of '{}'", struct_type),
+            ));
+        }
+        scope
+            .structs
+            .insert(tag.clone(), CType::Struct(struct_type.clone()));
+
+        let mut members: Vec<(String, CType)> = vec![];
+        for child in node.children.iter_mut() {
+            match &child.node_type {
+                NodeType::StructDecl(_) => self.define_struct(child)?,
+                NodeType::EnumDecl(_) => define_enum(child, self)?,
+                NodeType::MemberDecl(name, ctype) => {
+                    let ctype = self.resolve_type(ctype)?;
+                    if !ctype.is_complete() {
+                        return Err(CompileError::Semantic(
+                            child.span.clone(),
+                            format!("field '{}' has incomplete type '{}'", name, ctype),
+                        ));
+                    }
+                    if members.iter().any(|(member, _)| member == name) {
+                        return Err(CompileError::Semantic(
+                            child.span.clone(),
+                            format!("duplicate member '{}'", name),
+                        ));
+                    }
+                    members.push((name.clone(), ctype));
+                }
+                _ => panic!("{:?} is not a member declaration", child),
+            }
+        }
+
+        let definition = StructType::with_members(struct_type.kind.clone(), tag.clone(), members);
+        self.scopes
+            .last_mut()
+            .expect("Declaration outside of a scope")
+            .structs
+            .insert(tag, CType::Struct(Box::new(definition)));
+
+        Ok(())
+    }
+
+    fn mark_read(&mut self, variable: &Variable, span: &Span) {
+        let Some(usage) = variable.usage else {
+            return;
+        };
+        let usage = &mut self.usages[usage];
+        if usage.first_read.is_none() {
+            usage.first_read = Some(span.clone());
+        }
     }
 
-    fn get_var(&self, name: &String) -> Variable {
-        self.variables[name].clone()
+    fn mark_assigned(&mut self, variable: &Variable) {
+        if let Some(usage) = variable.usage {
+            self.usages[usage].is_assigned = true;
+        }
     }
 
-    fn len(&self) -> usize {
-        self.variables.len()
+    fn get_uninitialized_warnings(&self) -> Vec<CompileWarning> {
+        self.usages
+            .iter()
+            .filter(|usage| !usage.is_assigned)
+            .filter_map(|usage| {
+                usage.first_read.as_ref().map(|span| {
+                    CompileWarning::Uninitialized(
+                        span.clone(),
+                        format!("'{}' is used uninitialized", usage.name),
+                    )
+                })
+            })
+            .collect()
     }
 }
 
-fn scan_for_symbols(node: &mut ParseNode, symbols: &SymbolTable) {
-    match &node.node_type {
-        NodeType::Var(name, _) => {
-            let var = symbols.get_var(&name);
-            node.node_type = NodeType::Var(var.name, var.offset)
+fn scan_children(node: &mut ParseNode, symbols: &mut SymbolTable) -> Result<(), CompileError> {
+    for child in node.children.iter_mut() {
+        scan_for_symbols(child, symbols)?;
+        decay(child);
+    }
+
+    Ok(())
+}
+
+// An array that is used as a value is converted to a pointer to its first
+// element. Only the operands of '&' and sizeof, and the targets of assignments,
+// are scanned without decaying.
+fn decay(node: &mut ParseNode) {
+    if !matches!(
+        node.node_type,
+        NodeType::Var(_, _, _)
+            | NodeType::StaticVar(_, _)
+            | NodeType::StringLiteral(_)
+            | NodeType::Member(_, _, _)
+            | NodeType::UnaryOp(UnaryOp::Dereference)
+    ) {
+        return;
+    }
+    let CType::Array(element, _) = node.get_type() else {
+        return;
+    };
+
+    let span = node.span.clone();
+    let array = std::mem::replace(
+        node,
+        ParseNode::new(NodeType::Cast(CType::Pointer(element)), span.clone()),
+    );
+    let mut address_node = ParseNode::new(NodeType::UnaryOp(UnaryOp::AddressOf), span);
+    address_node.children.push(array);
+    node.children.push(address_node);
+}
+
+fn resolve_variable(node: &mut ParseNode, symbols: &SymbolTable) -> Result<Variable, CompileError> {
+    let NodeType::Var(name, _, _) = &node.node_type else {
+        panic!("{:?} is not a variable", node)
+    };
+
+    let var = symbols.get_var(name).ok_or_else(|| {
+        CompileError::Semantic(
+            node.span.clone(),
+            format!("use of undeclared identifier '{}'", name),
+        )
+    })?;
+    node.node_type = match (&var.label, var.value) {
+        (_, Some(value)) => NodeType::Const(value, var.ctype.clone()),
+        (Some(label), None) => NodeType::StaticVar(label.clone(), var.ctype.clone()),
+        (None, None) => NodeType::Var(var.name.clone(), var.offset, var.ctype.clone()),
+    };
+
+    Ok(var)
+}
+
+// Only variables, string literals, members and dereferenced pointers designate
+// an object, which can be assigned to unless it is an array
+fn verify_lvalue(node: &ParseNode, message: &str) -> Result<(), CompileError> {
+    match node.node_type {
+        NodeType::Var(_, _, _)
+        | NodeType::StaticVar(_, _)
+        | NodeType::StringLiteral(_)
+        | NodeType::Member(_, _, _)
+        | NodeType::UnaryOp(UnaryOp::Dereference) => Ok(()),
+        _ => Err(CompileError::Semantic(
+            node.span.clone(),
+            message.to_string(),
+        )),
+    }
+}
+
+// An object that is declared const, reached through a pointer to const, or a struct
+// with a const member can not be modified
+fn verify_modifiable(node: &ParseNode, operation: &str) -> Result<(), CompileError> {
+    if node.get_qualified_type().is_modifiable() {
+        return Ok(());
+    }
+
+    let object = match &node.node_type {
+        NodeType::Var(name, _, _) => format!("read-only variable '{}'", name),
+        // The label of a static variable in a block has its name between the name of
+        // the function and a number
+        NodeType::StaticVar(label, _) => format!(
+            "read-only variable '{}'",
+            label.split('.').rev().nth(1).unwrap_or(label)
+        ),
+        NodeType::Member(name, _, ctype) if ctype.is_modifiable() => {
+            format!("member '{}' in read-only object", name)
+        }
+        NodeType::Member(name, _, _) => format!("read-only member '{}'", name),
+        _ => "read-only location".to_string(),
+    };
+    Err(CompileError::Semantic(
+        node.span.clone(),
+        format!("{} of {}", operation, object),
+    ))
+}
+
+fn convert(node: &mut ParseNode, ctype: &CType) {
+    if node.get_type() != *ctype {
+        let cast_node = ParseNode::new(NodeType::Cast(ctype.clone()), node.span.clone());
+        let operand = std::mem::replace(node, cast_node);
+        node.children.push(operand);
+    }
+}
+
+fn promote(node: &mut ParseNode) {
+    convert(node, &node.get_type().promote());
+}
+
+// A void expression has no value, it can only be evaluated for its side effects
+fn verify_not_void(node: &ParseNode) -> Result<(), CompileError> {
+    if node.get_type().is_void() {
+        Err(CompileError::Semantic(
+            node.span.clone(),
+            "void value not ignored as it ought to be".to_string(),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+// A condition is compared with 0, so it has to be a scalar
+fn verify_condition(node: &ParseNode) -> Result<(), CompileError> {
+    verify_not_void(node)?;
+    if node.get_type().is_struct() {
+        return Err(CompileError::Semantic(
+            node.span.clone(),
+            "used struct type value where scalar is required".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+// Converts a value that is assigned, passed or returned to the type of the object
+// it ends up in. A struct is only compatible with the same struct.
+fn convert_assigned_value(node: &mut ParseNode, ctype: &CType) -> Result<(), CompileError> {
+    verify_not_void(node)?;
+    let ctype = ctype.unqualified();
+    let value_type = node.get_type();
+    if (value_type.is_struct() || ctype.is_struct()) && value_type != *ctype {
+        return Err(CompileError::Semantic(
+            node.span.clone(),
+            format!("incompatible type '{}', expected '{}'", value_type, ctype),
+        ));
+    }
+
+    convert(node, ctype);
+    Ok(())
+}
+
+// The size of an object is needed to access it, or to step over it with a pointer
+fn verify_complete(ctype: &CType, span: &Span) -> Result<(), CompileError> {
+    if ctype.is_complete() {
+        Ok(())
+    } else {
+        Err(CompileError::Semantic(
+            span.clone(),
+            format!("invalid use of incomplete type '{}'", ctype),
+        ))
+    }
+}
+
+fn convert_arguments(node: &mut ParseNode, symbols: &SymbolTable) -> Result<(), CompileError> {
+    let NodeType::FnCall(name, _, _) = &node.node_type else {
+        panic!("{:?} is not a function call", node)
+    };
+
+    // Functions that are not declared in the program return an int, and are called
+    // like variadic functions without parameters
+    let Some(function) = symbols.functions.get(name) else {
+        for argument in node.children.iter_mut() {
+            verify_not_void(argument)?;
+            promote(argument);
+        }
+        node.node_type = NodeType::FnCall(name.clone(), CType::Int, true);
+        return Ok(());
+    };
+
+    let amount = match node.children.len().cmp(&function.parameters.len()) {
+        Ordering::Less => Some("few"),
+        Ordering::Greater if !function.is_variadic => Some("many"),
+        _ => None,
+    };
+    if let Some(amount) = amount {
+        return Err(CompileError::Semantic(
+            node.span.clone(),
+            format!("too {} arguments to function '{}'", amount, name),
+        ));
+    }
+
+    let mut parameters = function.parameters.iter();
+    for argument in node.children.iter_mut() {
+        match parameters.next() {
+            Some(ctype) => convert_assigned_value(argument, ctype)?,
+            None => {
+                verify_not_void(argument)?;
+                promote(argument)
+            }
+        }
+    }
+    node.node_type = NodeType::FnCall(
+        name.clone(),
+        function.return_type.clone(),
+        function.is_variadic,
+    );
+
+    Ok(())
+}
+
+// Scales the integer operand of pointer arithmetic by the size of the pointee
+fn scale(node: &mut ParseNode, pointee: &CType) {
+    convert(node, &CType::Long);
+
+    if pointee.size() > 1 {
+        let span = node.span.clone();
+        let multiplication_node =
+            ParseNode::new(NodeType::BinaryOp(BinaryOp::Multiplication), span.clone());
+        let operand = std::mem::replace(node, multiplication_node);
+        node.children = vec![
+            operand,
+            ParseNode::new(NodeType::Const(pointee.size() as i64, CType::Long), span),
+        ];
+    }
+}
+
+// The difference of two pointers counts the elements between them, rather than the bytes
+fn convert_pointer_difference(node: &mut ParseNode, pointee: &CType) {
+    let span = node.span.clone();
+    let difference = std::mem::replace(
+        node,
+        ParseNode::new(NodeType::Cast(CType::Long), span.clone()),
+    );
+    node.children.push(difference);
+
+    if pointee.size() > 1 {
+        let division_node = ParseNode::new(NodeType::BinaryOp(BinaryOp::Division), span.clone());
+        let operand = std::mem::replace(node, division_node);
+        node.children = vec![
+            operand,
+            ParseNode::new(NodeType::Const(pointee.size() as i64, CType::Long), span),
+        ];
+    }
+}
+
+fn get_invalid_operands_error(node: &ParseNode) -> CompileError {
+    CompileError::Semantic(
+        node.span.clone(),
+        format!(
+            "invalid operands to binary operation (have '{}' and '{}')",
+            node.get_child(0).get_type(),
+            node.get_child(1).get_type()
+        ),
+    )
+}
+
+// Converts the operands to the type the operation is performed in. Pointer
+// arithmetic is rewritten to integer arithmetic on the addresses, with the
+// pointer as the left operand.
+fn convert_operands(node: &mut ParseNode, operation: &BinaryOp) -> Result<(), CompileError> {
+    verify_not_void(node.get_child(0))?;
+    verify_not_void(node.get_child(1))?;
+    let left_type = node.get_child(0).get_type();
+    let right_type = node.get_child(1).get_type();
+
+    if left_type.is_struct() || right_type.is_struct() {
+        return Err(get_invalid_operands_error(node));
+    }
+    if let (BinaryOp::Plus | BinaryOp::Minus, Some(pointee)) = (
+        operation,
+        left_type.get_pointee().or(right_type.get_pointee()),
+    ) {
+        verify_complete(pointee, &node.span)?;
+    }
+
+    match (operation, left_type.get_pointee(), right_type.get_pointee()) {
+        (BinaryOp::LogicalAnd | BinaryOp::LogicalOr, _, _) => {}
+        (BinaryOp::Plus, Some(_), Some(_)) => return Err(get_invalid_operands_error(node)),
+        (BinaryOp::Plus | BinaryOp::Minus, Some(pointee), None) => {
+            scale(&mut node.children[1], pointee)
+        }
+        (BinaryOp::Plus, None, Some(pointee)) => {
+            node.children.swap(0, 1);
+            scale(&mut node.children[1], pointee)
+        }
+        (BinaryOp::Minus, Some(pointee), Some(other)) => {
+            if pointee.unqualified() != other.unqualified() {
+                return Err(get_invalid_operands_error(node));
+            }
+            convert_pointer_difference(node, pointee)
+        }
+        (
+            BinaryOp::Less
+            | BinaryOp::LessOrEqual
+            | BinaryOp::Greater
+            | BinaryOp::GreaterOrEqual
+            | BinaryOp::Equal
+            | BinaryOp::NotEqual,
+            Some(_),
+            _,
+        ) => convert(&mut node.children[1], &left_type),
+        (
+            BinaryOp::Less
+            | BinaryOp::LessOrEqual
+            | BinaryOp::Greater
+            | BinaryOp::GreaterOrEqual
+            | BinaryOp::Equal
+            | BinaryOp::NotEqual,
+            None,
+            Some(_),
+        ) => convert(&mut node.children[0], &right_type),
+        (_, Some(_), _) | (_, _, Some(_)) => return Err(get_invalid_operands_error(node)),
+        (BinaryOp::ShiftLeft | BinaryOp::ShiftRight, None, None) => {
+            node.children.iter_mut().for_each(promote)
+        }
+        (_, None, None) => {
+            let ctype = get_common_type(&left_type, &right_type);
+            node.children
+                .iter_mut()
+                .for_each(|child| convert(child, &ctype));
+        }
+    }
+
+    Ok(())
+}
+
+// The values of a conditional are converted to a common type, C 6.5.15: integers
+// to their common type, and an integer to the pointer it is paired with. Structs
+// have to be the same struct, and a void value can only be paired with another.
+fn convert_conditional_operands(node: &mut ParseNode) -> Result<(), CompileError> {
+    verify_condition(node.get_child(0))?;
+
+    let true_type = node.get_child(1).get_type();
+    let false_type = node.get_child(2).get_type();
+    let ctype = match (&true_type, &false_type) {
+        _ if true_type == false_type => true_type.clone(),
+        _ if true_type.is_struct()
+            || false_type.is_struct()
+            || true_type.is_void()
+            || false_type.is_void() =>
+        {
+            return Err(CompileError::Semantic(
+                node.span.clone(),
+                "type mismatch in conditional expression".to_string(),
+            ))
         }
+        (CType::Pointer(_), _) => true_type.clone(),
+        (_, CType::Pointer(_)) => false_type.clone(),
+        _ => get_common_type(&true_type, &false_type),
+    };
+
+    convert(&mut node.children[1], &ctype);
+    convert(&mut node.children[2], &ctype);
+    Ok(())
+}
+
+// Operators that require an integer operand, pointers are only accepted by the
+// address and logical operators and by dereferencing, structs only by the
+// address operator
+fn convert_unary_operand(node: &mut ParseNode, operation: &UnaryOp) -> Result<(), CompileError> {
+    verify_not_void(node.get_child(0))?;
+    let ctype = node.get_child(0).get_type();
+
+    match (operation, ctype.get_pointee()) {
+        (UnaryOp::AddressOf, _) => Ok(()),
+        (UnaryOp::Dereference, Some(pointee)) => verify_complete(pointee, &node.span),
+        (UnaryOp::Dereference, None) => Err(CompileError::Semantic(
+            node.span.clone(),
+            format!("invalid type argument of unary '*' (have '{}')", ctype),
+        )),
+        (UnaryOp::LogicalNot, _) if !ctype.is_struct() => Ok(()),
+        _ if !ctype.is_integer() => Err(CompileError::Semantic(
+            node.span.clone(),
+            format!("wrong type argument to unary operator (have '{}')", ctype),
+        )),
         _ => {
+            promote(&mut node.children[0]);
+            Ok(())
+        }
+    }
+}
+
+// Only integers and pointers to complete types can be stepped
+fn verify_increment_operand(node: &ParseNode, operator: &str) -> Result<(), CompileError> {
+    let operand = node.get_child(0);
+    verify_lvalue(operand, &format!("lvalue required as {} operand", operator))?;
+    verify_modifiable(operand, operator)?;
+
+    let ctype = operand.get_type();
+    match ctype.get_pointee() {
+        Some(pointee) => verify_complete(pointee, &node.span),
+        None if ctype.is_struct() => Err(CompileError::Semantic(
+            node.span.clone(),
+            format!("wrong type argument to {} (have '{}')", operator, ctype),
+        )),
+        None => Ok(()),
+    }
+}
+
+fn get_excess_elements_error(span: &Span, ctype: &CType) -> CompileError {
+    let kind = match ctype {
+        CType::Struct(struct_type) if struct_type.kind == StructKind::Union => "union",
+        CType::Struct(_) => "struct",
+        CType::Array(_, _) => "array",
+        _ => "scalar",
+    };
+
+    CompileError::Semantic(
+        span.clone(),
+        format!("excess elements in {} initializer", kind),
+    )
+}
+
+// The expressions of an initializer are scanned before it is flattened, as a
+// struct may be initialized by an expression of its own type. A string literal
+// only decays once it is known not to initialize a char array.
+fn scan_initializer(node: &mut ParseNode, symbols: &mut SymbolTable) -> Result<(), CompileError> {
+    match node.node_type {
+        NodeType::InitializerList => {
             for child in node.children.iter_mut() {
-                scan_for_symbols(child, symbols)
+                scan_initializer(child, symbols)?;
+            }
+        }
+        NodeType::StringLiteral(_) => {}
+        _ => {
+            scan_for_symbols(node, symbols)?;
+            decay(node);
+        }
+    }
+
+    Ok(())
+}
+
+// An array of characters may be initialized by a string literal, optionally in
+// braces
+fn get_string_initializer<'a>(ctype: &CType, entry: &'a ParseNode) -> Option<&'a [u8]> {
+    let CType::Array(element, _) = ctype else {
+        return None;
+    };
+    if !matches!(element.unqualified(), CType::Char | CType::UnsignedChar) {
+        return None;
+    }
+
+    let string = match entry.children.as_slice() {
+        [string] if entry.node_type == NodeType::InitializerList => string,
+        _ => entry,
+    };
+    match &string.node_type {
+        NodeType::StringLiteral(bytes) => Some(bytes),
+        _ => None,
+    }
+}
+
+// Initializes the characters of the array with the bytes of the string, followed
+// by null characters. The terminating null character is left out if the array has
+// no room for it.
+fn flatten_string(
+    ctype: &CType,
+    bytes: &[u8],
+    offset: usize,
+    span: &Span,
+    initializers: &mut Vec<ParseNode>,
+) -> Result<(), CompileError> {
+    let CType::Array(element, length) = ctype else {
+        panic!("{} is not an array", ctype)
+    };
+    if bytes.len() > *length {
+        return Err(CompileError::Semantic(
+            span.clone(),
+            format!("initializer-string for array of '{}' is too long", element),
+        ));
+    }
+
+    for i in 0..*length {
+        let byte = bytes.get(i).copied().unwrap_or(0);
+        let value = ParseNode::new(
+            NodeType::Const(element.truncate(byte as i64), *element.clone()),
+            span.clone(),
+        );
+        initializers.push(get_initializer(offset + i, element, value)?);
+    }
+
+    Ok(())
+}
+
+// A scalar may be initialized with a single expression in braces
+fn get_scalar_initializer(entry: &ParseNode, ctype: &CType) -> Result<ParseNode, CompileError> {
+    if entry.node_type != NodeType::InitializerList {
+        return Ok(entry.clone());
+    }
+
+    match entry.children.as_slice() {
+        [value] if value.node_type != NodeType::InitializerList => Ok(value.clone()),
+        [value] => Err(CompileError::Semantic(
+            value.span.clone(),
+            "braces around scalar initializer".to_string(),
+        )),
+        _ => Err(get_excess_elements_error(&entry.children[1].span, ctype)),
+    }
+}
+
+fn get_initializer(
+    offset: usize,
+    ctype: &CType,
+    value: ParseNode,
+) -> Result<ParseNode, CompileError> {
+    let mut value = value;
+    decay(&mut value);
+    convert_assigned_value(&mut value, ctype)?;
+
+    let mut node = ParseNode::new(
+        NodeType::Initializer(offset, ctype.unqualified().clone()),
+        value.span.clone(),
+    );
+    node.children.push(value);
+    Ok(node)
+}
+
+// The elements of an array and the members of a struct are initialized in order,
+// only the first member of a union is
+fn get_elements(ctype: &CType) -> Vec<(usize, CType)> {
+    match ctype.unqualified() {
+        CType::Array(element, length) => (0..*length)
+            .map(|i| (i * element.size(), *element.clone()))
+            .collect(),
+        CType::Struct(struct_type) => {
+            let members = struct_type.get_members().iter();
+            let amount = match struct_type.kind {
+                StructKind::Struct => members.len(),
+                StructKind::Union => 1,
+            };
+            members
+                .take(amount)
+                .map(|member| (member.offset, member.ctype.clone()))
+                .collect()
+        }
+        ctype => panic!("{} has no elements", ctype),
+    }
+}
+
+// Scalars without an initializer are initialized with 0
+fn pad_initializer(
+    ctype: &CType,
+    offset: usize,
+    span: &Span,
+    initializers: &mut Vec<ParseNode>,
+) -> Result<(), CompileError> {
+    if !ctype.is_array() && !ctype.is_struct() {
+        let zero = ParseNode::new(NodeType::Const(0, CType::Int), span.clone());
+        initializers.push(get_initializer(offset, ctype, zero)?);
+        return Ok(());
+    }
+
+    for (element_offset, element) in get_elements(ctype) {
+        pad_initializer(&element, offset + element_offset, span, initializers)?;
+    }
+
+    Ok(())
+}
+
+// Lines up the initializers with the scalars of an object, at their offset in the
+// object. An initializer list initializes one element, otherwise the braces around
+// the element are elided and it takes as many initializers as it has scalars.
+fn flatten_initializer(
+    ctype: &CType,
+    entries: &[ParseNode],
+    pos: &mut usize,
+    offset: usize,
+    span: &Span,
+    initializers: &mut Vec<ParseNode>,
+) -> Result<(), CompileError> {
+    let Some(entry) = entries.get(*pos) else {
+        return pad_initializer(ctype, offset, span, initializers);
+    };
+    let is_list = entry.node_type == NodeType::InitializerList;
+
+    if let Some(bytes) = get_string_initializer(ctype, entry) {
+        *pos += 1;
+        return flatten_string(ctype, bytes, offset, &entry.span, initializers);
+    }
+
+    match ctype.unqualified() {
+        CType::Struct(_) if !is_list && entry.get_type() == *ctype.unqualified() => {
+            *pos += 1;
+            initializers.push(get_initializer(offset, ctype, entry.clone())?);
+            Ok(())
+        }
+        CType::Array(_, _) | CType::Struct(_) if is_list => {
+            *pos += 1;
+            flatten_initializer_list(ctype, entry, offset, initializers)
+        }
+        CType::Array(_, _) | CType::Struct(_) => {
+            flatten_elements(ctype, entries, pos, offset, span, initializers)
+        }
+        _ => {
+            *pos += 1;
+            let value = get_scalar_initializer(entry, ctype)?;
+            initializers.push(get_initializer(offset, ctype, value)?);
+            Ok(())
+        }
+    }
+}
+
+fn flatten_elements(
+    ctype: &CType,
+    entries: &[ParseNode],
+    pos: &mut usize,
+    offset: usize,
+    span: &Span,
+    initializers: &mut Vec<ParseNode>,
+) -> Result<(), CompileError> {
+    for (element_offset, element) in get_elements(ctype) {
+        flatten_initializer(
+            &element,
+            entries,
+            pos,
+            offset + element_offset,
+            span,
+            initializers,
+        )?;
+    }
+
+    Ok(())
+}
+
+// The initializers in braces around an array or struct initialize its elements
+fn flatten_initializer_list(
+    ctype: &CType,
+    list: &ParseNode,
+    offset: usize,
+    initializers: &mut Vec<ParseNode>,
+) -> Result<(), CompileError> {
+    let mut pos = 0;
+    flatten_elements(
+        ctype,
+        &list.children,
+        &mut pos,
+        offset,
+        &list.span,
+        initializers,
+    )?;
+
+    match list.children.get(pos) {
+        Some(excess) => Err(get_excess_elements_error(&excess.span, ctype)),
+        None => Ok(()),
+    }
+}
+
+// An array without a length gets as many elements as there are initializers for,
+// or as there are characters in its string including the terminating null
+fn complete_array_type(ctype: &CType, initializer: &ParseNode) -> Result<CType, CompileError> {
+    let CType::Array(element, _) = ctype else {
+        panic!("{} is not an array", ctype)
+    };
+    if let Some(bytes) = get_string_initializer(ctype, initializer) {
+        return Ok(CType::Array(element.clone(), bytes.len() + 1));
+    }
+    if initializer.node_type != NodeType::InitializerList {
+        return Err(CompileError::Semantic(
+            initializer.span.clone(),
+            "invalid initializer".to_string(),
+        ));
+    }
+
+    let mut pos = 0;
+    let mut length = 0;
+    while pos < initializer.children.len() {
+        flatten_initializer(
+            element,
+            &initializer.children,
+            &mut pos,
+            0,
+            &initializer.span,
+            &mut vec![],
+        )?;
+        length += 1;
+    }
+
+    Ok(CType::Array(element.clone(), length))
+}
+
+// Rewrites the initializer of an array or struct to a flat list with an
+// initializer for each of its scalars, other initializers are converted to the
+// type of the variable
+fn convert_initializer(ctype: &CType, initializer: &ParseNode) -> Result<ParseNode, CompileError> {
+    let is_list = initializer.node_type == NodeType::InitializerList;
+
+    match ctype.unqualified() {
+        CType::Array(_, _) if get_string_initializer(ctype, initializer).is_some() => {
+            let mut list = ParseNode::new(NodeType::InitializerList, initializer.span.clone());
+            let entries = std::slice::from_ref(initializer);
+            let span = &initializer.span;
+            flatten_initializer(ctype, entries, &mut 0, 0, span, &mut list.children)?;
+            Ok(list)
+        }
+        CType::Array(_, _) | CType::Struct(_) if is_list => {
+            let mut list = ParseNode::new(NodeType::InitializerList, initializer.span.clone());
+            flatten_initializer_list(ctype, initializer, 0, &mut list.children)?;
+            Ok(list)
+        }
+        CType::Array(_, _) => Err(CompileError::Semantic(
+            initializer.span.clone(),
+            "invalid initializer".to_string(),
+        )),
+        _ => {
+            let mut value = get_scalar_initializer(initializer, ctype)?;
+            decay(&mut value);
+            convert_assigned_value(&mut value, ctype)?;
+            Ok(value)
+        }
+    }
+}
+
+// The type of a variable that is declared again, an array may get its length from
+// either declaration. Returns None if the types conflict.
+fn get_composite_type(previous: &CType, ctype: &CType) -> Option<CType> {
+    match (previous, ctype) {
+        _ if previous == ctype => Some(ctype.clone()),
+        (CType::Array(element, 0), CType::Array(other, _))
+        | (CType::Array(other, _), CType::Array(element, 0))
+            if element == other =>
+        {
+            let length = match (previous, ctype) {
+                (CType::Array(_, 0), CType::Array(_, length)) => *length,
+                (CType::Array(_, length), _) => *length,
+                _ => 0,
+            };
+            Some(CType::Array(element.clone(), length))
+        }
+        _ => None,
+    }
+}
+
+// Completes an array without a length from its initializer, which is then already
+// scanned. Only a declaration that does not define the variable may leave its type
+// incomplete. Returns the type and whether the initializer was scanned.
+fn get_declared_type(
+    node: &mut ParseNode,
+    symbols: &mut SymbolTable,
+    is_definition: bool,
+) -> Result<(CType, bool), CompileError> {
+    let (NodeType::FnParam(name, _, ctype) | NodeType::VarDecl(name, _, ctype)) = &node.node_type
+    else {
+        panic!("{:?} is not a declaration", node)
+    };
+    let name = name.clone();
+    let ctype = symbols.resolve_type(ctype)?;
+
+    match ctype {
+        CType::Void => Err(CompileError::Semantic(
+            node.span.clone(),
+            format!("variable or field '{}' declared void", name),
+        )),
+        CType::Array(_, 0) if !node.children.is_empty() => {
+            scan_initializer(&mut node.children[0], symbols)?;
+            Ok((complete_array_type(&ctype, node.get_child(0))?, true))
+        }
+        _ if !is_definition => Ok((ctype, false)),
+        CType::Array(_, 0) => Err(CompileError::Semantic(
+            node.span.clone(),
+            format!("array size missing in '{}'", name),
+        )),
+        _ if !ctype.is_complete() => Err(CompileError::Semantic(
+            node.span.clone(),
+            format!("storage size of '{}' isn't known", name),
+        )),
+        _ => Ok((ctype, false)),
+    }
+}
+
+// The value of a constant expression, C 6.6, is an integer or the address of a
+// static variable or string literal plus an offset in bytes
+enum Constant {
+    Integer(i64),
+    Address(ParseNode, i64),
+}
+
+// Performs the operation in the type of the operands, like the generated code does
+fn evaluate_integer_operation(operation: &BinaryOp, a: i64, b: i64, ctype: &CType) -> Option<i64> {
+    let is_signed = ctype.is_signed();
+    let (unsigned_a, unsigned_b) = (a as u64, b as u64);
+
+    Some(match operation {
+        BinaryOp::Plus => a.wrapping_add(b),
+        BinaryOp::Minus => a.wrapping_sub(b),
+        BinaryOp::Multiplication => a.wrapping_mul(b),
+        BinaryOp::Division | BinaryOp::Modulo if b == 0 => return None,
+        BinaryOp::Division if is_signed => a.wrapping_div(b),
+        BinaryOp::Division => (unsigned_a / unsigned_b) as i64,
+        BinaryOp::Modulo if is_signed => a.wrapping_rem(b),
+        BinaryOp::Modulo => (unsigned_a % unsigned_b) as i64,
+        BinaryOp::Less if is_signed => (a < b) as i64,
+        BinaryOp::Less => (unsigned_a < unsigned_b) as i64,
+        BinaryOp::LessOrEqual if is_signed => (a <= b) as i64,
+        BinaryOp::LessOrEqual => (unsigned_a <= unsigned_b) as i64,
+        BinaryOp::Greater if is_signed => (a > b) as i64,
+        BinaryOp::Greater => (unsigned_a > unsigned_b) as i64,
+        BinaryOp::GreaterOrEqual if is_signed => (a >= b) as i64,
+        BinaryOp::GreaterOrEqual => (unsigned_a >= unsigned_b) as i64,
+        BinaryOp::Equal => (a == b) as i64,
+        BinaryOp::NotEqual => (a != b) as i64,
+        BinaryOp::LogicalAnd => (a != 0 && b != 0) as i64,
+        BinaryOp::LogicalOr => (a != 0 || b != 0) as i64,
+        BinaryOp::BitwiseAnd => a & b,
+        BinaryOp::BitwiseOr => a | b,
+        BinaryOp::BitwiseXor => a ^ b,
+        BinaryOp::ShiftLeft => a.wrapping_shl(b as u32),
+        BinaryOp::ShiftRight if is_signed => a.wrapping_shr(b as u32),
+        BinaryOp::ShiftRight => unsigned_a.wrapping_shr(b as u32) as i64,
+    })
+}
+
+// The symbol pass already scaled the integer that is added to an address
+fn evaluate_binary_operation(node: &ParseNode, operation: &BinaryOp) -> Option<Constant> {
+    let left = evaluate_constant(node.get_child(0))?;
+    let right = evaluate_constant(node.get_child(1))?;
+
+    match (operation, left, right) {
+        (_, Constant::Integer(a), Constant::Integer(b)) => {
+            let value = evaluate_integer_operation(operation, a, b, &node.get_child(0).get_type())?;
+            Some(Constant::Integer(node.get_type().truncate(value)))
+        }
+        (BinaryOp::Plus, Constant::Address(object, offset), Constant::Integer(b))
+        | (BinaryOp::Plus, Constant::Integer(b), Constant::Address(object, offset)) => {
+            Some(Constant::Address(object, offset + b))
+        }
+        (BinaryOp::Minus, Constant::Address(object, offset), Constant::Integer(b)) => {
+            Some(Constant::Address(object, offset - b))
+        }
+        (BinaryOp::Minus, Constant::Address(object, offset), Constant::Address(other, b))
+            if object.node_type == other.node_type =>
+        {
+            Some(Constant::Integer(offset - b))
+        }
+        _ => None,
+    }
+}
+
+// The address of a static variable or string literal, or of a member or element
+// of one
+fn evaluate_address(node: &ParseNode) -> Option<Constant> {
+    match &node.node_type {
+        NodeType::StaticVar(_, _) | NodeType::StringLiteral(_) => {
+            Some(Constant::Address(node.clone(), 0))
+        }
+        NodeType::Member(_, member_offset, _) => match evaluate_address(node.get_child(0))? {
+            Constant::Address(object, offset) => {
+                Some(Constant::Address(object, offset + *member_offset as i64))
+            }
+            Constant::Integer(_) => None,
+        },
+        NodeType::UnaryOp(UnaryOp::Dereference) => match evaluate_constant(node.get_child(0))? {
+            address @ Constant::Address(_, _) => Some(address),
+            Constant::Integer(_) => None,
+        },
+        _ => None,
+    }
+}
+
+// The length of an array is a positive integer constant, evaluated in the scope
+// of the declaration it is in
+fn evaluate_array_length(
+    length: &ParseNode,
+    symbols: &mut SymbolTable,
+) -> Result<usize, CompileError> {
+    let mut length = length.clone();
+    scan_for_symbols(&mut length, symbols)?;
+
+    match evaluate_constant(&length) {
+        Some(Constant::Integer(value)) if length.get_type().is_integer() && value > 0 => {
+            Ok(value as usize)
+        }
+        _ => Err(CompileError::Semantic(
+            length.span,
+            "array length must be a positive integer constant".to_string(),
+        )),
+    }
+}
+
+// Evaluates an expression in the initializer of a static variable, which is
+// None if it is not constant
+fn evaluate_constant(node: &ParseNode) -> Option<Constant> {
+    match &node.node_type {
+        NodeType::Const(value, _) => Some(Constant::Integer(*value)),
+        NodeType::Cast(CType::Void) => None,
+        // An address only fits in a pointer or an integer of the same size
+        NodeType::Cast(ctype) => match evaluate_constant(node.get_child(0))? {
+            Constant::Integer(value) => Some(Constant::Integer(ctype.truncate(value))),
+            address if ctype.size() == QUADWORD_LENGTH && *ctype != CType::Bool => Some(address),
+            Constant::Address(_, _) => None,
+        },
+        NodeType::UnaryOp(UnaryOp::AddressOf) => evaluate_address(node.get_child(0)),
+        NodeType::UnaryOp(operation) => {
+            let Constant::Integer(value) = evaluate_constant(node.get_child(0))? else {
+                return None;
+            };
+            let value = match operation {
+                UnaryOp::Plus => value,
+                UnaryOp::Minus => value.wrapping_neg(),
+                UnaryOp::BitwiseNot => !value,
+                UnaryOp::LogicalNot => (value == 0) as i64,
+                UnaryOp::AddressOf | UnaryOp::Dereference => return None,
+            };
+            Some(Constant::Integer(node.get_type().truncate(value)))
+        }
+        NodeType::BinaryOp(operation) => evaluate_binary_operation(node, operation),
+        NodeType::Conditional => match evaluate_constant(node.get_child(0))? {
+            Constant::Integer(0) => evaluate_constant(node.get_child(2)),
+            _ => evaluate_constant(node.get_child(1)),
+        },
+        _ => None,
+    }
+}
+
+// A static variable is initialized with constants, each scalar at its offset in
+// the variable
+fn get_static_initializer(
+    ctype: &CType,
+    initializer: &ParseNode,
+) -> Result<ParseNode, CompileError> {
+    let initializer = convert_initializer(ctype, initializer)?;
+    let mut list = if initializer.node_type == NodeType::InitializerList {
+        initializer
+    } else {
+        let mut list = ParseNode::new(NodeType::InitializerList, initializer.span.clone());
+        list.children.push(get_initializer(0, ctype, initializer)?);
+        list
+    };
+
+    for entry in list.children.iter_mut() {
+        let NodeType::Initializer(_, ctype) = &entry.node_type else {
+            panic!("{:?} is not a flattened initializer", entry)
+        };
+        let value = entry.get_child(0);
+        let constant = evaluate_constant(value).ok_or_else(|| {
+            CompileError::Semantic(
+                value.span.clone(),
+                "initializer element is not constant".to_string(),
+            )
+        })?;
+
+        let span = value.span.clone();
+        entry.children[0] = match constant {
+            Constant::Integer(value) => ParseNode::new(NodeType::Const(value, ctype.clone()), span),
+            Constant::Address(object, offset) => {
+                let mut address =
+                    ParseNode::new(NodeType::StaticAddress(offset, ctype.clone()), span);
+                address.children.push(object);
+                address
+            }
+        };
+    }
+
+    Ok(list)
+}
+
+// A variable at file scope, or declared static or extern in a block, has static
+// storage duration. Its declaration is left empty, as the variable is initialized
+// by the data of the binary.
+fn scan_static_declaration(
+    node: &mut ParseNode,
+    storage_class: &StorageClass,
+    symbols: &mut SymbolTable,
+) -> Result<(), CompileError> {
+    let NodeType::VarDecl(name, _, _) = &node.node_type else {
+        panic!("{:?} is not a variable declaration", node)
+    };
+    let name = name.clone();
+    let has_initializer = !node.children.is_empty();
+
+    if *storage_class == StorageClass::Extern && has_initializer && !symbols.is_file_scope() {
+        return Err(CompileError::Semantic(
+            node.span.clone(),
+            format!("'{}' has both 'extern' and initializer", name),
+        ));
+    }
+
+    let is_definition = *storage_class != StorageClass::Extern || has_initializer;
+    let (ctype, is_scanned) = get_declared_type(node, symbols, is_definition)?;
+    let var = symbols.add_static(&name, &ctype, &node.span, storage_class, is_definition)?;
+
+    if let Some(initializer) = node.children.first_mut() {
+        if !is_scanned {
+            scan_initializer(initializer, symbols)?;
+        }
+        let initializer = get_static_initializer(&var.ctype, initializer)?;
+        symbols.set_static_initializer(&var, initializer)?;
+    }
+
+    node.node_type = NodeType::Empty;
+    node.children.clear();
+
+    Ok(())
+}
+
+// The constants of an enum are defined in order, so a value may refer to the
+// constants before it. A constant without a value is one more than the previous
+// one, which has to fit in an int.
+fn define_enum(node: &mut ParseNode, symbols: &mut SymbolTable) -> Result<(), CompileError> {
+    let mut next_value = Some(0);
+
+    for enumerator in node.children.iter_mut() {
+        let NodeType::Enumerator(name) = &enumerator.node_type else {
+            panic!("{:?} is not an enumerator", enumerator)
+        };
+        let name = name.clone();
+
+        let value = match enumerator.children.first_mut() {
+            Some(value) => {
+                scan_for_symbols(value, symbols)?;
+                match evaluate_constant(value) {
+                    Some(Constant::Integer(constant)) if value.get_type().is_integer() => {
+                        CType::Int.truncate(constant)
+                    }
+                    _ => {
+                        return Err(CompileError::Semantic(
+                            value.span.clone(),
+                            format!("enumerator value for '{}' is not an integer constant", name),
+                        ))
+                    }
+                }
+            }
+            None => next_value.ok_or_else(|| {
+                CompileError::Semantic(
+                    enumerator.span.clone(),
+                    "overflow in enumeration values".to_string(),
+                )
+            })?,
+        };
+
+        symbols.add_constant(&name, value, &enumerator.span)?;
+        next_value = (value < i32::MAX as i64).then_some(value + 1);
+    }
+
+    Ok(())
+}
+
+// A cast converts a scalar to another scalar type, the conversion itself is done
+// by the generator like the conversions the symbol pass inserts
+fn verify_cast(node: &ParseNode, ctype: &CType) -> Result<(), CompileError> {
+    let operand_type = node.get_child(0).get_type();
+
+    let message = match ctype {
+        // Any value can be discarded
+        CType::Void => return Ok(()),
+        _ if operand_type.is_void() => "void value not ignored as it ought to be".to_string(),
+        CType::Array(_, _) => "cast specifies array type".to_string(),
+        CType::Struct(_) => "conversion to non-scalar type requested".to_string(),
+        _ if operand_type.is_struct() && ctype.is_pointer() => {
+            "aggregate value used where a pointer was expected".to_string()
+        }
+        _ if operand_type.is_struct() => {
+            "aggregate value used where an integer was expected".to_string()
+        }
+        _ => return Ok(()),
+    };
+
+    Err(CompileError::Semantic(node.span.clone(), message))
+}
+
+// The labels of a nested switch belong to that switch, rather than the one whose
+// values are converted
+fn convert_case_labels(
+    node: &mut ParseNode,
+    ctype: &CType,
+    values: &mut Vec<i64>,
+    has_default: &mut bool,
+) -> Result<(), CompileError> {
+    match node.node_type {
+        NodeType::Switch => return Ok(()),
+        NodeType::Case => {
+            let value = &mut node.children[0];
+            let NodeType::Const(constant, _) = value.node_type else {
+                panic!("{:?} is not a case value", value)
+            };
+
+            let constant = ctype.truncate(constant);
+            if values.contains(&constant) {
+                return Err(CompileError::Semantic(
+                    value.span.clone(),
+                    "duplicate case value".to_string(),
+                ));
             }
+            values.push(constant);
+            value.node_type = NodeType::Const(constant, ctype.clone());
+        }
+        NodeType::Default if *has_default => {
+            return Err(CompileError::Semantic(
+                node.span.clone(),
+                "multiple default labels in one switch".to_string(),
+            ))
         }
+        NodeType::Default => *has_default = true,
+        _ => (),
+    }
+
+    for child in node.children.iter_mut() {
+        convert_case_labels(child, ctype, values, has_default)?;
     }
+
+    Ok(())
 }
 
-pub fn symbolpass(node: &mut ParseNode) {
+fn scan_for_symbols(node: &mut ParseNode, symbols: &mut SymbolTable) -> Result<(), CompileError> {
     match &node.node_type {
-        NodeType::Fn(fn_name, _) => {
-            let mut offset = QUADWORD_LENGTH;
-            let mut symbols = SymbolTable::new();
+        NodeType::Var(_, _, _) => {
+            let var = resolve_variable(node, symbols)?;
+            symbols.mark_read(&var, &node.span);
+        }
+        // The scope of a variable starts right after its declarator, so it is
+        // already visible in its own initializer. An array without a length is
+        // only declared once its length follows from the initializer.
+        NodeType::FnParam(name, _, _) | NodeType::VarDecl(name, _, _) => {
+            let name = name.clone();
+            let has_initializer = !node.children.is_empty();
+            let (ctype, is_scanned) = get_declared_type(node, symbols, true)?;
+
+            // The elements of arrays and structs are assigned individually
+            let is_assigned = matches!(node.node_type, NodeType::FnParam(_, _, _))
+                || has_initializer
+                || ctype.is_array()
+                || ctype.is_struct();
+            let var = symbols.add(&name, &ctype, &node.span, is_assigned)?;
+
+            // Only the variable keeps the qualifiers, the declaration initializes it
+            let declared_type = var.ctype.unqualified().clone();
+            node.node_type = match node.node_type {
+                NodeType::FnParam(_, _, _) => {
+                    NodeType::FnParam(var.name, var.offset, declared_type)
+                }
+                _ => NodeType::VarDecl(var.name, var.offset, declared_type),
+            };
+
+            if let Some(initializer) = node.children.first_mut() {
+                if !is_scanned {
+                    scan_initializer(initializer, symbols)?;
+                }
+                *initializer = convert_initializer(&var.ctype, initializer)?;
+            }
+        }
+        // Compound statements, and selection and iteration statements with their
+        // bodies, are blocks with their own scope
+        NodeType::Block | NodeType::If | NodeType::While | NodeType::DoWhile | NodeType::For => {
+            symbols.enter_scope();
+            scan_children(node, symbols)?;
+            symbols.leave_scope();
+
+            // The condition of a for statement may be left out
+            let condition = match node.node_type {
+                NodeType::If | NodeType::While => node.children.first(),
+                NodeType::DoWhile | NodeType::For => node.children.get(1),
+                _ => None,
+            };
+            if let Some(condition) = condition.filter(|child| child.node_type != NodeType::Empty) {
+                verify_condition(condition)?;
+            }
+        }
+        // The controlling expression is promoted, and the case values are converted
+        // to its type to be compared with it
+        NodeType::Switch => {
+            symbols.enter_scope();
+            scan_children(node, symbols)?;
+            symbols.leave_scope();
+
+            let ctype = node.get_child(0).get_type();
+            if !ctype.is_integer() {
+                return Err(CompileError::Semantic(
+                    node.get_child(0).span.clone(),
+                    "switch quantity not an integer".to_string(),
+                ));
+            }
+            promote(&mut node.children[0]);
+            convert_case_labels(
+                &mut node.children[1],
+                &ctype.promote(),
+                &mut vec![],
+                &mut false,
+            )?;
+        }
+        NodeType::Case => {
+            scan_children(node, symbols)?;
+
+            let value = node.get_child(0);
+            let ctype = value.get_type();
+            let constant = match evaluate_constant(value) {
+                Some(Constant::Integer(constant)) if ctype.is_integer() => constant,
+                _ => {
+                    return Err(CompileError::Semantic(
+                        value.span.clone(),
+                        "case label does not reduce to an integer constant".to_string(),
+                    ))
+                }
+            };
+            node.children[0] = ParseNode::new(NodeType::Const(constant, ctype), value.span.clone());
+        }
+        NodeType::Declaration(storage_class) => {
+            let storage_class = storage_class.clone();
+            let is_static = symbols.is_file_scope() || storage_class != StorageClass::Auto;
 
             for child in node.children.iter_mut() {
-                scan_for_symbols(child, &symbols);
-                match &child.node_type {
-                    NodeType::VarDecl(name, _) => {
-                        symbols.add(&name, offset);
-                        child.node_type = NodeType::VarDecl(name.clone(), offset);
-                        offset += QUADWORD_LENGTH;
+                match child.node_type {
+                    NodeType::VarDecl(_, _, _) if is_static => {
+                        scan_static_declaration(child, &storage_class, symbols)?
                     }
-                    _ => {}
+                    _ => scan_for_symbols(child, symbols)?,
+                }
+            }
+        }
+        NodeType::StructDecl(_) => symbols.define_struct(node)?,
+        NodeType::EnumDecl(_) => define_enum(node, symbols)?,
+        // The casts the symbol pass inserts are never scanned, so this is one
+        // written in the program. A cast gives a value, which is unqualified.
+        NodeType::Cast(ctype) => {
+            let ctype = symbols.resolve_type(ctype)?.unqualified().clone();
+            scan_children(node, symbols)?;
+            verify_cast(node, &ctype)?;
+            node.node_type = NodeType::Cast(ctype);
+        }
+        // The struct is resolved again, as a pointer to it may have been declared
+        // before its definition, such as a member pointing to its own struct
+        NodeType::Member(name, _, _) => {
+            let name = name.clone();
+            scan_children(node, symbols)?;
+
+            let ctype = symbols.resolve_type(&node.get_child(0).get_type())?;
+            let CType::Struct(struct_type) = &ctype else {
+                return Err(CompileError::Semantic(
+                    node.span.clone(),
+                    format!(
+                        "request for member '{}' in something not a structure or union",
+                        name
+                    ),
+                ));
+            };
+            verify_complete(&ctype, &node.span)?;
+
+            let member = struct_type.get_member(&name).ok_or_else(|| {
+                CompileError::Semantic(
+                    node.span.clone(),
+                    format!("'{}' has no member named '{}'", ctype, name),
+                )
+            })?;
+            node.node_type =
+                NodeType::Member(name, member.offset, symbols.resolve_type(&member.ctype)?);
+        }
+        // A function returning void may return the value of a void expression
+        NodeType::Return => {
+            scan_children(node, symbols)?;
+            let return_type = symbols.return_type.clone();
+            let message = match node.children.first_mut() {
+                Some(value) if !return_type.is_void() => {
+                    convert_assigned_value(value, &return_type)?;
+                    None
+                }
+                Some(value) if !value.get_type().is_void() => {
+                    Some("'return' with a value, in function returning void")
+                }
+                None if !return_type.is_void() => {
+                    Some("'return' with no value, in function returning non-void")
+                }
+                _ => None,
+            };
+            if let Some(message) = message {
+                return Err(CompileError::Semantic(
+                    node.span.clone(),
+                    message.to_string(),
+                ));
+            }
+        }
+        NodeType::FnCall(_, _, _) => {
+            scan_children(node, symbols)?;
+            convert_arguments(node, symbols)?;
+        }
+        // Taking the address of a variable lets it be assigned through the pointer
+        NodeType::UnaryOp(UnaryOp::AddressOf) => {
+            let operand = &mut node.children[0];
+            if matches!(operand.node_type, NodeType::Var(_, _, _)) {
+                let var = resolve_variable(operand, symbols)?;
+                symbols.mark_assigned(&var);
+            } else {
+                scan_for_symbols(operand, symbols)?;
+            }
+
+            verify_lvalue(node.get_child(0), "lvalue required as unary '&' operand")?;
+        }
+        // The operand of sizeof is not evaluated, only its type is used
+        NodeType::SizeOf => {
+            scan_for_symbols(&mut node.children[0], symbols)?;
+            let ctype = symbols.resolve_type(&node.get_child(0).get_type())?;
+            verify_complete(&ctype, &node.span)?;
+            let size = ctype.size();
+            node.node_type = NodeType::Const(size as i64, CType::UnsignedLong);
+            node.children.clear();
+        }
+        NodeType::UnaryOp(operation) => {
+            let operation = operation.clone();
+            scan_children(node, symbols)?;
+            convert_unary_operand(node, &operation)?;
+        }
+        NodeType::BinaryOp(operation) => {
+            let operation = operation.clone();
+            scan_children(node, symbols)?;
+            convert_operands(node, &operation)?;
+        }
+        NodeType::Conditional => {
+            scan_children(node, symbols)?;
+            convert_conditional_operands(node)?;
+        }
+        // The target of a plain assignment is written, not read
+        NodeType::Assignment => {
+            scan_for_symbols(&mut node.children[1], symbols)?;
+            decay(&mut node.children[1]);
+
+            let target = &mut node.children[0];
+            if matches!(target.node_type, NodeType::Var(_, _, _)) {
+                let var = resolve_variable(target, symbols)?;
+                symbols.mark_assigned(&var);
+            } else {
+                scan_for_symbols(target, symbols)?;
+            }
+
+            verify_lvalue(
+                node.get_child(0),
+                "lvalue required as left operand of assignment",
+            )?;
+            let ctype = node.get_child(0).get_type();
+            if ctype.is_array() {
+                return Err(CompileError::Semantic(
+                    node.span.clone(),
+                    format!("assignment to expression with array type '{}'", ctype),
+                ));
+            }
+            verify_modifiable(node.get_child(0), "assignment")?;
+            convert_assigned_value(&mut node.children[1], &ctype)?;
+        }
+        // The value is converted to the type the operation is performed in, the
+        // generator converts the target to it and the result back
+        NodeType::CompoundAssignment(operation) => {
+            let operation = operation.clone();
+            scan_children(node, symbols)?;
+            verify_lvalue(
+                node.get_child(0),
+                "lvalue required as left operand of assignment",
+            )?;
+            verify_modifiable(node.get_child(0), "assignment")?;
+
+            verify_not_void(node.get_child(1))?;
+            let target_type = node.get_child(0).get_type();
+            let value_type = node.get_child(1).get_type();
+
+            match (&operation, target_type.get_pointee()) {
+                _ if target_type.is_struct() || value_type.is_struct() => {
+                    return Err(get_invalid_operands_error(node))
+                }
+                (BinaryOp::Plus | BinaryOp::Minus, Some(pointee)) if !value_type.is_pointer() => {
+                    verify_complete(pointee, &node.span)?;
+                    scale(&mut node.children[1], pointee)
+                }
+                _ if target_type.is_pointer() || value_type.is_pointer() => {
+                    return Err(get_invalid_operands_error(node))
+                }
+                (BinaryOp::ShiftLeft | BinaryOp::ShiftRight, _) => promote(&mut node.children[1]),
+                _ => {
+                    let ctype = get_common_type(
+                        &node.get_child(0).get_type(),
+                        &node.get_child(1).get_type(),
+                    );
+                    convert(&mut node.children[1], &ctype);
                 }
             }
+        }
+        NodeType::PreIncrement | NodeType::PostIncrement => {
+            scan_children(node, symbols)?;
+            verify_increment_operand(node, "increment")?;
+        }
+        NodeType::PreDecrement | NodeType::PostDecrement => {
+            scan_children(node, symbols)?;
+            verify_increment_operand(node, "decrement")?;
+        }
+        _ => scan_children(node, symbols)?,
+    }
+
+    Ok(())
+}
+
+// The qualifiers of the parameters are not part of the type of the function
+fn get_function(node: &ParseNode) -> Option<(String, Function)> {
+    let (name, return_type, is_variadic) = match &node.node_type {
+        NodeType::Fn(name, _, return_type, _) => (name, return_type, false),
+        NodeType::FnDecl(name, return_type, is_variadic, _) => (name, return_type, *is_variadic),
+        _ => return None,
+    };
+
+    let parameters = node
+        .children
+        .iter()
+        .filter_map(|child| match &child.node_type {
+            NodeType::FnParam(_, _, ctype) => Some(ctype.unqualified().clone()),
+            _ => None,
+        })
+        .collect();
+
+    Some((
+        name.clone(),
+        Function {
+            return_type: return_type.clone(),
+            parameters,
+            is_variadic,
+        },
+    ))
+}
+
+// A function declared static is only visible in its file, and stays so in the
+// declarations that follow without a storage class, C 6.2.2
+fn resolve_linkage(nodes: &mut [ParseNode]) -> Result<(), CompileError> {
+    let mut linkages: HashMap<String, bool> = HashMap::new();
+
+    for node in nodes.iter_mut() {
+        let (NodeType::Fn(name, _, _, is_global) | NodeType::FnDecl(name, _, _, is_global)) =
+            &mut node.node_type
+        else {
+            continue;
+        };
+
+        match linkages.get(name) {
+            Some(true) if !*is_global => {
+                return Err(CompileError::Semantic(
+                    node.span.clone(),
+                    format!(
+                        "static declaration of '{}' follows non-static declaration",
+                        name
+                    ),
+                ))
+            }
+            Some(previous) => *is_global = *previous,
+            None => {
+                linkages.insert(name.clone(), *is_global);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// A function may be declared any number of times but defined only once, and all
+// of its declarations have to agree on its signature
+fn get_functions(nodes: &[ParseNode]) -> Result<HashMap<String, Function>, CompileError> {
+    let mut functions: HashMap<String, Function> = HashMap::new();
+    let mut defined = HashSet::new();
+
+    for node in nodes {
+        let Some((name, function)) = get_function(node) else {
+            continue;
+        };
+
+        if matches!(node.node_type, NodeType::Fn(_, _, _, _)) && !defined.insert(name.clone()) {
+            return Err(CompileError::Semantic(
+                node.span.clone(),
+                format!("redefinition of '{}'", name),
+            ));
+        }
+        match functions.get(&name) {
+            Some(previous) if *previous != function => {
+                return Err(CompileError::Semantic(
+                    node.span.clone(),
+                    format!("conflicting types for '{}'", name),
+                ))
+            }
+            _ => {
+                functions.insert(name, function);
+            }
+        }
+    }
+
+    Ok(functions)
+}
+
+fn get_called_functions(node: &ParseNode, names: &mut Vec<String>) {
+    if let NodeType::FnCall(name, _, _) = &node.node_type {
+        names.push(name.clone());
+    }
+    for child in node.children.iter() {
+        get_called_functions(child, names);
+    }
+}
+
+// The functions that are called but not defined in the program are linked from
+// elsewhere, such as the C library
+fn get_extern_functions(node: &ParseNode) -> Vec<ParseNode> {
+    let defined: HashSet<&String> = node
+        .children
+        .iter()
+        .filter_map(|child| match &child.node_type {
+            NodeType::Fn(name, _, _, _) => Some(name),
+            _ => None,
+        })
+        .collect();
+    let mut called = vec![];
+    get_called_functions(node, &mut called);
+    called.sort();
+    called.dedup();
+
+    called
+        .into_iter()
+        .filter(|name| !defined.contains(name))
+        .map(|name| ParseNode::new(NodeType::ExternDecl(name), node.span.clone()))
+        .collect()
+}
+
+// Resolves the struct types in the signature of a function to the structs
+// defined before it, which must be complete as they are passed by value
+fn resolve_signature(node: &mut ParseNode, symbols: &mut SymbolTable) -> Result<(), CompileError> {
+    let (NodeType::Fn(_, _, return_type, _) | NodeType::FnDecl(_, return_type, _, _)) =
+        &mut node.node_type
+    else {
+        return Ok(());
+    };
+    *return_type = symbols.resolve_type(return_type)?.unqualified().clone();
+    if !return_type.is_complete() && !return_type.is_void() {
+        return Err(CompileError::Semantic(
+            node.span.clone(),
+            "return type is an incomplete type".to_string(),
+        ));
+    }
+
+    for child in node.children.iter_mut() {
+        let NodeType::FnParam(name, offset, ctype) = &child.node_type else {
+            continue;
+        };
+        let ctype = symbols.resolve_type(ctype)?;
+        if !ctype.is_complete() {
+            return Err(CompileError::Semantic(
+                child.span.clone(),
+                format!("parameter '{}' has incomplete type '{}'", name, ctype),
+            ));
+        }
+        child.node_type = NodeType::FnParam(name.clone(), *offset, ctype);
+    }
+
+    Ok(())
+}
+
+// The labels of a function are in scope in all of it, so a goto may jump forwards
+// and backwards, and into and out of blocks
+fn collect_labels(node: &ParseNode, labels: &mut HashSet<String>) -> Result<(), CompileError> {
+    if let NodeType::Label(name) = &node.node_type {
+        if !labels.insert(name.clone()) {
+            return Err(CompileError::Semantic(
+                node.span.clone(),
+                format!("duplicate label '{}'", name),
+            ));
+        }
+    }
+
+    for child in node.children.iter() {
+        collect_labels(child, labels)?;
+    }
+
+    Ok(())
+}
+
+// Labels become labels of the assembly that are local to the function, every
+// goto has to jump to one of them
+fn resolve_labels(
+    node: &mut ParseNode,
+    labels: &HashSet<String>,
+    fn_name: &str,
+) -> Result<(), CompileError> {
+    match &node.node_type {
+        NodeType::Label(name) => {
+            node.node_type = NodeType::Label(format!(".L{}.{}", fn_name, name));
+        }
+        NodeType::Goto(name) if !labels.contains(name) => {
+            return Err(CompileError::Semantic(
+                node.span.clone(),
+                format!("label '{}' used but not defined", name),
+            ))
+        }
+        NodeType::Goto(name) => {
+            node.node_type = NodeType::Goto(format!(".L{}.{}", fn_name, name));
+        }
+        _ => (),
+    }
+
+    for child in node.children.iter_mut() {
+        resolve_labels(child, labels, fn_name)?;
+    }
+
+    Ok(())
+}
+
+// Returns the warnings and the static variables declared in the function
+fn scan_function(
+    node: &mut ParseNode,
+    functions: &HashMap<String, Function>,
+    file_scope: Scope,
+) -> Result<(Vec<CompileWarning>, Vec<StaticObject>), CompileError> {
+    let NodeType::Fn(fn_name, _, return_type, is_global) = &node.node_type else {
+        return Ok((vec![], vec![]));
+    };
+    let fn_name = fn_name.clone();
+    let return_type = return_type.clone();
+    let is_global = *is_global;
+    let mut symbols = SymbolTable::new(functions, file_scope, fn_name.clone(), return_type.clone());
+
+    // The parameters share the scope of the outermost block of the function
+    symbols.enter_scope();
+    scan_children(node, &mut symbols)?;
+    symbols.leave_scope();
+
+    let mut labels = HashSet::new();
+    collect_labels(node, &mut labels)?;
+    resolve_labels(node, &labels, &fn_name)?;
+
+    // Keeps the stack aligned to the size of the values that are pushed onto it
+    let frame_size = symbols.max_stack.next_multiple_of(QUADWORD_LENGTH);
+    node.node_type = NodeType::Fn(fn_name, frame_size, return_type, is_global);
+
+    Ok((symbols.get_uninitialized_warnings(), symbols.statics))
+}
+
+// Every static variable that is defined is emitted once, with the merged
+// declarations at file scope. The ones that are only declared are defined in
+// another file.
+fn get_static_declarations(statics: Vec<StaticObject>) -> Vec<ParseNode> {
+    let defined: HashSet<String> = statics
+        .iter()
+        .filter(|object| object.is_defined)
+        .map(|object| object.label.clone())
+        .collect();
+    let mut declared = HashSet::new();
+
+    statics
+        .into_iter()
+        .filter_map(|object| {
+            if object.is_defined {
+                let mut node = ParseNode::new(
+                    NodeType::StaticDecl(
+                        object.label,
+                        object.ctype.unqualified().clone(),
+                        object.is_global,
+                    ),
+                    object.span,
+                );
+                node.children.extend(object.initializer);
+                Some(node)
+            } else if !defined.contains(&object.label) && declared.insert(object.label.clone()) {
+                Some(ParseNode::new(
+                    NodeType::ExternDecl(object.label),
+                    object.span,
+                ))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
 
-            node.node_type = NodeType::Fn(fn_name.clone(), symbols.len())
+pub fn symbolpass(node: &mut ParseNode) -> Result<Vec<CompileWarning>, CompileError> {
+    let mut warnings = vec![];
+
+    // Functions may be called before they are defined, so their signatures are
+    // collected first, along with the structs defined at file scope before each
+    // function
+    let no_functions = HashMap::new();
+    let mut file_symbols =
+        SymbolTable::new(&no_functions, Scope::new(0), String::new(), CType::Int);
+    let mut file_scopes = vec![];
+    for child in node.children.iter_mut() {
+        match child.node_type {
+            NodeType::Fn(_, _, _, _) => {
+                resolve_signature(child, &mut file_symbols)?;
+                file_scopes.push(file_symbols.scopes[0].clone());
+            }
+            NodeType::FnDecl(_, _, _, _) => resolve_signature(child, &mut file_symbols)?,
+            _ => scan_for_symbols(child, &mut file_symbols)?,
         }
-        _ => {}
     }
+
+    resolve_linkage(&mut node.children)?;
+    let functions = get_functions(&node.children)?;
+    let mut statics = file_symbols.statics;
+
+    let functions_and_scopes = node
+        .children
+        .iter_mut()
+        .filter(|child| matches!(child.node_type, NodeType::Fn(_, _, _, _)))
+        .zip(file_scopes);
+    for (child, file_scope) in functions_and_scopes {
+        let (mut function_warnings, mut function_statics) =
+            scan_function(child, &functions, file_scope)?;
+        warnings.append(&mut function_warnings);
+        statics.append(&mut function_statics);
+    }
+
+    node.children.append(&mut get_static_declarations(statics));
+    node.children.append(&mut get_extern_functions(node));
+
+    Ok(warnings)
 }
@@ -0,0 +1,401 @@
+use std::fmt;
+
+use crate::parser::ParseNode;
+
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum CType {
+    Void, // an incomplete type without values
+    Bool,
+    Char, // plain char is signed on x86-64
+    UnsignedChar,
+    Short,
+    UnsignedShort,
+    Int,
+    UnsignedInt,
+    Long,
+    UnsignedLong,
+    LongLong,
+    UnsignedLongLong,
+    Pointer(Box<CType>),      // pointee
+    Array(Box<CType>, usize), // element, length, which is 0 until known from the initializer
+    Struct(Box<StructType>),  // also unions
+    // An array with a constant expression as its length, which the symbol pass
+    // evaluates where the array is declared
+    UnevaluatedArray(Box<CType>, Box<ParseNode>), // element, length
+    // A type qualified with const, an array is qualified through its elements
+    Const(Box<CType>),
+}
+
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum StructKind {
+    Struct,
+    Union,
+}
+
+// A member of a struct or union, at its offset from the start of the object
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct Member {
+    pub name: String,
+    pub ctype: CType,
+    pub offset: usize,
+}
+
+// A struct or union is identified by its tag, unnamed ones get a tag from their
+// location. The parser only refers to the tag, the members are known once the
+// symbol pass resolves the tag to its definition.
+#[derive(Eq, Clone, Debug)]
+pub struct StructType {
+    pub kind: StructKind,
+    pub tag: String,
+    pub members: Option<Vec<Member>>, // None while the type is incomplete
+}
+
+impl StructType {
+    pub fn new(kind: StructKind, tag: String) -> StructType {
+        StructType {
+            kind,
+            tag,
+            members: None,
+        }
+    }
+
+    // Lays out the members in order of declaration, each at the next offset that
+    // fits its alignment. The members of a union all start at offset 0.
+    pub fn with_members(
+        kind: StructKind,
+        tag: String,
+        members: Vec<(String, CType)>,
+    ) -> StructType {
+        let mut end: usize = 0;
+        let members = members
+            .into_iter()
+            .map(|(name, ctype)| {
+                let offset = match kind {
+                    StructKind::Struct => end.next_multiple_of(ctype.alignment()),
+                    StructKind::Union => 0,
+                };
+                end = offset + ctype.size();
+                Member {
+                    name,
+                    ctype,
+                    offset,
+                }
+            })
+            .collect();
+
+        StructType {
+            kind,
+            tag,
+            members: Some(members),
+        }
+    }
+
+    pub fn get_member(&self, name: &str) -> Option<&Member> {
+        self.members
+            .as_ref()?
+            .iter()
+            .find(|member| member.name == name)
+    }
+
+    pub fn get_members(&self) -> &[Member] {
+        self.members
+            .as_ref()
+            .unwrap_or_else(|| panic!("{} is incomplete", self))
+    }
+
+    // The object ends after its last byte, padded to a multiple of its alignment
+    // so that the members of the elements of an array stay aligned
+    fn size(&self) -> usize {
+        self.get_members()
+            .iter()
+            .map(|member| member.offset + member.ctype.size())
+            .max()
+            .unwrap_or(0)
+            .next_multiple_of(self.alignment())
+    }
+
+    fn alignment(&self) -> usize {
+        self.get_members()
+            .iter()
+            .map(|member| member.ctype.alignment())
+            .max()
+            .unwrap_or(1)
+    }
+}
+
+// Two struct types are the same type if they have the same tag, whether the
+// members are known or not
+impl PartialEq for StructType {
+    fn eq(&self, other: &StructType) -> bool {
+        self.kind == other.kind && self.tag == other.tag
+    }
+}
+
+impl fmt::Display for StructType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            StructKind::Struct => write!(f, "struct {}", self.tag),
+            StructKind::Union => write!(f, "union {}", self.tag),
+        }
+    }
+}
+
+impl CType {
+    pub fn size(&self) -> usize {
+        match self {
+            CType::Bool | CType::Char | CType::UnsignedChar => 1,
+            CType::Short | CType::UnsignedShort => 2,
+            CType::Int | CType::UnsignedInt => 4,
+            CType::Long | CType::UnsignedLong | CType::LongLong | CType::UnsignedLongLong => 8,
+            CType::Pointer(_) => 8,
+            CType::Array(element, length) => element.size() * length,
+            CType::Struct(struct_type) => struct_type.size(),
+            CType::UnevaluatedArray(_, _) => panic!("The length of {:?} is not evaluated", self),
+            CType::Void => panic!("void has no size"),
+            CType::Const(ctype) => ctype.size(),
+        }
+    }
+
+    // Arrays are aligned like their elements, structs like their most aligned
+    // member, and all other types to their size
+    pub fn alignment(&self) -> usize {
+        match self {
+            CType::Array(element, _) | CType::UnevaluatedArray(element, _) => element.alignment(),
+            CType::Struct(struct_type) => struct_type.alignment(),
+            CType::Const(ctype) => ctype.alignment(),
+            ctype => ctype.size(),
+        }
+    }
+
+    pub fn is_signed(&self) -> bool {
+        matches!(
+            self.unqualified(),
+            CType::Char | CType::Short | CType::Int | CType::Long | CType::LongLong
+        )
+    }
+
+    pub fn max_value(&self) -> u64 {
+        match self {
+            CType::Bool => 1,
+            CType::Char => i8::MAX as u64,
+            CType::UnsignedChar => u8::MAX as u64,
+            CType::Short => i16::MAX as u64,
+            CType::UnsignedShort => u16::MAX as u64,
+            CType::Int => i32::MAX as u64,
+            CType::UnsignedInt => u32::MAX as u64,
+            CType::Long | CType::LongLong => i64::MAX as u64,
+            CType::UnsignedLong
+            | CType::UnsignedLongLong
+            | CType::Pointer(_)
+            | CType::Array(_, _)
+            | CType::Struct(_)
+            | CType::UnevaluatedArray(_, _)
+            | CType::Void => u64::MAX,
+            CType::Const(ctype) => ctype.max_value(),
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        !matches!(
+            self.unqualified(),
+            CType::Pointer(_)
+                | CType::Array(_, _)
+                | CType::Struct(_)
+                | CType::UnevaluatedArray(_, _)
+                | CType::Void
+        )
+    }
+
+    pub fn is_pointer(&self) -> bool {
+        matches!(self.unqualified(), CType::Pointer(_))
+    }
+
+    pub fn is_array(&self) -> bool {
+        matches!(self, CType::Array(_, _))
+    }
+
+    pub fn is_struct(&self) -> bool {
+        matches!(self.unqualified(), CType::Struct(_))
+    }
+
+    pub fn is_void(&self) -> bool {
+        matches!(self.unqualified(), CType::Void)
+    }
+
+    pub fn is_const(&self) -> bool {
+        matches!(self, CType::Const(_))
+    }
+
+    // The type qualified with const, C 6.7.3
+    pub fn to_const(&self) -> CType {
+        match self {
+            CType::Const(_) => self.clone(),
+            CType::Array(element, length) => CType::Array(Box::new(element.to_const()), *length),
+            CType::UnevaluatedArray(element, length) => {
+                CType::UnevaluatedArray(Box::new(element.to_const()), length.clone())
+            }
+            ctype => CType::Const(Box::new(ctype.clone())),
+        }
+    }
+
+    // The type without its qualifiers, which is the type of the value read from an
+    // object of this type
+    pub fn unqualified(&self) -> &CType {
+        match self {
+            CType::Const(ctype) => ctype,
+            ctype => ctype,
+        }
+    }
+
+    // Whether an object of this type may be assigned to, which a struct may not if
+    // any of its members is const, C 6.3.2.1
+    pub fn is_modifiable(&self) -> bool {
+        match self {
+            CType::Const(_) => false,
+            CType::Array(element, _) => element.is_modifiable(),
+            CType::Struct(struct_type) => struct_type
+                .members
+                .iter()
+                .flatten()
+                .all(|member| member.ctype.is_modifiable()),
+            _ => true,
+        }
+    }
+
+    // Whether the size of an object of this type is known
+    pub fn is_complete(&self) -> bool {
+        match self {
+            CType::Array(element, length) => *length > 0 && element.is_complete(),
+            CType::Struct(struct_type) => struct_type.members.is_some(),
+            CType::UnevaluatedArray(_, _) | CType::Void => false,
+            CType::Const(ctype) => ctype.is_complete(),
+            _ => true,
+        }
+    }
+
+    pub fn get_pointee(&self) -> Option<&CType> {
+        match self.unqualified() {
+            CType::Pointer(pointee) => Some(pointee),
+            _ => None,
+        }
+    }
+
+    // Integer conversion rank, C 6.3.1.1
+    fn rank(&self) -> usize {
+        match self {
+            CType::Bool => 0,
+            CType::Char | CType::UnsignedChar => 1,
+            CType::Short | CType::UnsignedShort => 2,
+            CType::Int | CType::UnsignedInt => 3,
+            CType::Long | CType::UnsignedLong => 4,
+            CType::LongLong | CType::UnsignedLongLong => 5,
+            CType::Pointer(_)
+            | CType::Array(_, _)
+            | CType::Struct(_)
+            | CType::UnevaluatedArray(_, _)
+            | CType::Void => {
+                panic!("Only integer types have an integer conversion rank")
+            }
+            CType::Const(ctype) => ctype.rank(),
+        }
+    }
+
+    fn to_unsigned(&self) -> CType {
+        match self {
+            CType::Char => CType::UnsignedChar,
+            CType::Short => CType::UnsignedShort,
+            CType::Int => CType::UnsignedInt,
+            CType::Long => CType::UnsignedLong,
+            CType::LongLong => CType::UnsignedLongLong,
+            ctype => ctype.clone(),
+        }
+    }
+
+    // Integer promotions, C 6.3.1.1: the types ranked below int are promoted to int,
+    // which can represent all of their values
+    pub fn promote(&self) -> CType {
+        if self.is_integer() && self.rank() < CType::Int.rank() {
+            CType::Int
+        } else {
+            self.clone()
+        }
+    }
+
+    // Wraps the value around to the range of the type, as the generated code does
+    // with the values in registers. Converting to _Bool compares with 0 instead.
+    pub fn truncate(&self, value: i64) -> i64 {
+        if *self.unqualified() == CType::Bool {
+            return (value != 0) as i64;
+        }
+
+        match 64 - 8 * self.size().min(8) as u32 {
+            0 => value,
+            unused_bits if self.is_signed() => (value << unused_bits) >> unused_bits,
+            unused_bits => ((value as u64) << unused_bits >> unused_bits) as i64,
+        }
+    }
+
+    // Whether every value of this type is also a value of the other type
+    pub fn fits_in(&self, other: &CType) -> bool {
+        self == other || (self.size() < other.size() && (other.is_signed() || !self.is_signed()))
+    }
+}
+
+// Usual arithmetic conversions for integer operands, C 6.3.1.8
+pub fn get_common_type(left: &CType, right: &CType) -> CType {
+    let left = left.promote();
+    let right = right.promote();
+
+    if left == right {
+        return left;
+    }
+
+    if left.is_signed() == right.is_signed() {
+        return if left.rank() > right.rank() {
+            left
+        } else {
+            right
+        };
+    }
+
+    let (signed, unsigned) = if left.is_signed() {
+        (left, right)
+    } else {
+        (right, left)
+    };
+
+    if unsigned.rank() >= signed.rank() {
+        unsigned
+    } else if unsigned.fits_in(&signed) {
+        signed
+    } else {
+        signed.to_unsigned()
+    }
+}
+
+// Spells the type the way it is written in C
+impl fmt::Display for CType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CType::Void => write!(f, "void"),
+            CType::Bool => write!(f, "_Bool"),
+            CType::Char => write!(f, "char"),
+            CType::UnsignedChar => write!(f, "unsigned char"),
+            CType::Short => write!(f, "short"),
+            CType::UnsignedShort => write!(f, "unsigned short"),
+            CType::Int => write!(f, "int"),
+            CType::UnsignedInt => write!(f, "unsigned int"),
+            CType::Long => write!(f, "long"),
+            CType::UnsignedLong => write!(f, "unsigned long"),
+            CType::LongLong => write!(f, "long long"),
+            CType::UnsignedLongLong => write!(f, "unsigned long long"),
+            CType::Pointer(pointee) if pointee.is_pointer() => write!(f, "{}*", pointee),
+            CType::Pointer(pointee) => write!(f, "{} *", pointee),
+            CType::Array(element, length) => write!(f, "{}[{}]", element, length),
+            CType::Struct(struct_type) => write!(f, "{}", struct_type),
+            CType::UnevaluatedArray(element, _) => write!(f, "{}[]", element),
+            CType::Const(ctype) if ctype.is_pointer() => write!(f, "{}const", ctype),
+            CType::Const(ctype) => write!(f, "const {}", ctype),
+        }
+    }
+}
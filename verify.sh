@@ -48,3 +48,47 @@ assert function-three-params 6
 assert function-four-params 24
 assert function-with-local-variables 12
 assert function-calling-function 12
+assert function-eight-params 77
+assert if-else 7
+assert factorial-recursive 120
+assert fibonacci-recursive 55
+assert while-break 4
+assert do-while-continue 5
+assert for-loop 6
+assert nested-loops 9
+assert comparison-operators 23
+assert logical-operators 26
+assert bitwise-operators 11
+assert shift-operators 80
+assert division-modulo 138
+assert operator-precedence 1
+assert left-associativity 52
+assert parentheses 105
+assert unary-operators 58
+assert expression-associativity 113
+assert nested-function-calls 18
+assert mixed-precedence 96
+assert comments 16
+assert integer-literals 8
+assert character-constants 72
+assert block-scoping 32
+assert assignment 31
+assert compound-assignment 31
+assert increment-decrement 151
+assert uninitialized-declarations 42
+assert integer-types 255
+assert unsigned-arithmetic 255
+assert pointers 103
+assert arrays 239
+assert structs 159
+assert globals 250
+assert strings 190
+assert libc-calls 88
+assert preprocessor 203
+assert typedef-enum 180
+assert switch 108
+assert goto 246
+assert division 120
+assert conditional 56
+assert void 140
+assert const 87